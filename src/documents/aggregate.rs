@@ -0,0 +1,334 @@
+use super::*;
+use crate::values::FirestoreValue;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+///
+/// Aggregates the documents that match a query on the server, without fetching them.
+/// Created via [`QueryBuilder::aggregate`].
+///
+/// Each aggregation has an alias that identifies its value in the [`AggregationResult`].
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{field, QueryBuilder};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let result = QueryBuilder::collection("tests")
+///     .filter(field("a_string").eq("abc")?)
+///     .aggregate()
+///     .count("count")
+///     .sum("total", "an_int")
+///     .avg("average", "an_int")
+///     .run(&session)?;
+/// println!("{:?} {:?} {:?}", result.count("count"), result.get_f64("total"), result.get_f64("average"));
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct AggregationQuery {
+    query: QueryBuilder,
+    aggregations: Vec<dto::Aggregation>,
+}
+
+impl QueryBuilder {
+    /// Aggregates the matching documents, for example counts them. See [`AggregationQuery`].
+    pub fn aggregate(self) -> AggregationQuery {
+        AggregationQuery {
+            query: self,
+            aggregations: Vec::new(),
+        }
+    }
+}
+
+impl AggregationQuery {
+    fn aggregation(mut self, alias: impl Into<String>, aggregation: dto::Aggregation) -> Self {
+        self.aggregations.push(dto::Aggregation {
+            alias: Some(alias.into()),
+            ..aggregation
+        });
+        self
+    }
+
+    /// Counts the matching documents
+    pub fn count(self, alias: impl Into<String>) -> Self {
+        self.aggregation(
+            alias,
+            dto::Aggregation {
+                count: Some(dto::Count::default()),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Counts the matching documents, but stops counting at the given number.
+    /// This limits the number of index entries that are scanned.
+    pub fn count_up_to(self, alias: impl Into<String>, up_to: u64) -> Self {
+        self.aggregation(
+            alias,
+            dto::Aggregation {
+                count: Some(dto::Count {
+                    up_to: Some(up_to.to_string()),
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Sums up the numeric values of the given field. Non-numeric values are ignored.
+    /// The sum is an integer if all values are integers and the sum does not overflow, and a double otherwise.
+    pub fn sum(self, alias: impl Into<String>, field_path: impl Into<String>) -> Self {
+        self.aggregation(
+            alias,
+            dto::Aggregation {
+                sum: Some(dto::Sum {
+                    field: dto::FieldReference {
+                        field_path: field_path.into(),
+                    },
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Averages the numeric values of the given field. Non-numeric values are ignored.
+    /// The average is null if there are no numeric values.
+    pub fn avg(self, alias: impl Into<String>, field_path: impl Into<String>) -> Self {
+        self.aggregation(
+            alias,
+            dto::Aggregation {
+                avg: Some(dto::Avg {
+                    field: dto::FieldReference {
+                        field_path: field_path.into(),
+                    },
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn request(&self) -> dto::RunAggregationQueryRequest {
+        let query_request = self.query.query_request();
+        dto::RunAggregationQueryRequest {
+            structured_aggregation_query: Some(dto::StructuredAggregationQuery {
+                structured_query: query_request.structured_query,
+                aggregations: self.aggregations.clone(),
+            }),
+            transaction: query_request.transaction,
+            read_time: query_request.read_time,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Executes this aggregation.
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    pub fn run(&self, auth: &impl FirebaseAuthBearer) -> Result<AggregationResult> {
+        let url = self.query.action_url(auth.project_id(), "runAggregationQuery");
+        let responses = run_query(auth, &url, &self.request(), self.query.collection_id())?;
+        AggregationResult::from_responses(responses)
+    }
+
+    ///
+    /// [Async] Executes this aggregation.
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    pub async fn run_async(&self, auth: &impl FirebaseAuthBearer) -> Result<AggregationResult> {
+        let url = self.query.action_url(auth.project_id(), "runAggregationQuery");
+        let responses = run_query_async(auth, &url, &self.request(), self.query.collection_id()).await?;
+        AggregationResult::from_responses(responses)
+    }
+}
+
+/// The result of an [`AggregationQuery`]. The values are identified by the aliases of the aggregations.
+#[derive(Clone, Debug, Default)]
+pub struct AggregationResult {
+    /// The aggregated values by alias
+    pub values: HashMap<String, FirestoreValue>,
+    /// The time at which the aggregation was computed
+    pub read_time: Option<DateTime<Utc>>,
+}
+
+impl AggregationResult {
+    fn from_responses(responses: Vec<dto::RunAggregationQueryResponse>) -> Result<Self> {
+        let mut result = AggregationResult::default();
+        for response in responses {
+            if let Some(read_time) = response.read_time {
+                result.read_time = Some(
+                    DateTime::parse_from_rfc3339(&read_time)
+                        .map_err(|_| FirebaseError::Generic("Failed to parse rfc3339 date from 'read_time' field"))?
+                        .with_timezone(&Utc),
+                );
+            }
+            let fields = response.result.and_then(|r| r.aggregate_fields).unwrap_or_default();
+            for (alias, value) in fields {
+                result.values.insert(alias, FirestoreValue::try_from(&value)?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the value of the aggregation with the given alias
+    pub fn get(&self, alias: &str) -> Option<&FirestoreValue> {
+        self.values.get(alias)
+    }
+
+    /// Returns the value of a count aggregation
+    pub fn count(&self, alias: &str) -> Option<i64> {
+        self.get(alias).and_then(FirestoreValue::as_i64)
+    }
+
+    /// Returns the value of a sum or average aggregation. Integer sums are converted to f64,
+    /// use [`AggregationResult::get`] to keep their precision. None is returned for the average of no values.
+    pub fn get_f64(&self, alias: &str) -> Option<f64> {
+        self.get(alias).and_then(FirestoreValue::as_f64)
+    }
+}
+
+///
+/// Counts the documents that match the given query, without fetching them.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{self, QueryBuilder};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let count = documents::count(&session, &QueryBuilder::collection("tests"))?;
+/// println!("{} documents", count);
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query
+pub fn count(auth: &impl FirebaseAuthBearer, query: &QueryBuilder) -> Result<i64> {
+    let result = query.clone().aggregate().count("count").run(auth)?;
+    Ok(result.count("count").unwrap_or_default())
+}
+
+///
+/// [Async] Counts the documents that match the given query, without fetching them.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query
+pub async fn count_async(auth: &impl FirebaseAuthBearer, query: &QueryBuilder) -> Result<i64> {
+    let result = query.clone().aggregate().count("count").run_async(auth).await?;
+    Ok(result.count("count").unwrap_or_default())
+}
+
+///
+/// Sums up the numeric values of the given field of all documents that match the given query.
+///
+/// The sum is a [`FirestoreValue::Integer`] if all values are integers and the sum does not overflow,
+/// and a [`FirestoreValue::Double`] otherwise.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query
+/// * 'field_path' The field, for example "price" or "a_map.a"
+pub fn sum(auth: &impl FirebaseAuthBearer, query: &QueryBuilder, field_path: &str) -> Result<FirestoreValue> {
+    let result = query.clone().aggregate().sum("sum", field_path).run(auth)?;
+    Ok(sum_value(result))
+}
+
+///
+/// [Async] Sums up the numeric values of the given field of all documents that match the given query.
+/// See [`sum`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query
+/// * 'field_path' The field, for example "price" or "a_map.a"
+pub async fn sum_async(
+    auth: &impl FirebaseAuthBearer,
+    query: &QueryBuilder,
+    field_path: &str,
+) -> Result<FirestoreValue> {
+    let result = query.clone().aggregate().sum("sum", field_path).run_async(auth).await?;
+    Ok(sum_value(result))
+}
+
+/// The value of the "sum" aggregation. The sum of no values is the integer 0.
+fn sum_value(mut result: AggregationResult) -> FirestoreValue {
+    result.values.remove("sum").unwrap_or(FirestoreValue::Integer(0))
+}
+
+///
+/// Averages the numeric values of the given field of all documents that match the given query.
+/// Returns None if there are no numeric values.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query
+/// * 'field_path' The field, for example "price" or "a_map.a"
+pub fn avg(auth: &impl FirebaseAuthBearer, query: &QueryBuilder, field_path: &str) -> Result<Option<f64>> {
+    let result = query.clone().aggregate().avg("avg", field_path).run(auth)?;
+    Ok(result.get_f64("avg"))
+}
+
+///
+/// [Async] Averages the numeric values of the given field of all documents that match the given query.
+/// Returns None if there are no numeric values.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query
+/// * 'field_path' The field, for example "price" or "a_map.a"
+pub async fn avg_async(auth: &impl FirebaseAuthBearer, query: &QueryBuilder, field_path: &str) -> Result<Option<f64>> {
+    let result = query.clone().aggregate().avg("avg", field_path).run_async(auth).await?;
+    Ok(result.get_f64("avg"))
+}
+
+#[test]
+fn aggregation_query_test() {
+    let query = QueryBuilder::collection("users/u1/orders")
+        .filter(field("state").eq("open").unwrap())
+        .aggregate()
+        .count_up_to("count", 1000)
+        .sum("total", "price")
+        .avg("average", "price");
+    assert_eq!(
+        query.query.action_url("p", "runAggregationQuery"),
+        "https://firestore.googleapis.com/v1/projects/p/databases/(default)/documents/users/u1:runAggregationQuery"
+    );
+    let json = serde_json::to_value(query.request()).unwrap();
+    assert_eq!(
+        json["structuredAggregationQuery"]["aggregations"],
+        serde_json::json!([
+            { "alias": "count", "count": { "upTo": "1000" } },
+            { "alias": "total", "sum": { "field": { "fieldPath": "price" } } },
+            { "alias": "average", "avg": { "field": { "fieldPath": "price" } } }
+        ])
+    );
+    assert_eq!(
+        json["structuredAggregationQuery"]["structuredQuery"]["from"][0]["collectionId"],
+        "orders"
+    );
+
+    let responses: Vec<dto::RunAggregationQueryResponse> = serde_json::from_value(serde_json::json!([{
+        "result": { "aggregateFields": {
+            "count": { "integerValue": "3" },
+            "total": { "doubleValue": 7.5 },
+            "average": { "nullValue": null }
+        } },
+        "readTime": "2020-01-22T10:00:00.123Z"
+    }]))
+    .unwrap();
+    let result = AggregationResult::from_responses(responses).unwrap();
+    assert_eq!(result.count("count"), Some(3));
+    assert_eq!(result.get_f64("total"), Some(7.5));
+    assert_eq!(result.get_f64("average"), None);
+    assert!(result.read_time.is_some());
+}
@@ -0,0 +1,293 @@
+use super::*;
+
+/// Collects write operations to be committed atomically in a single request.
+///
+/// Either all writes succeed or none of them are applied.
+/// A batch can contain up to 500 writes.
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let obj = DemoDTO { a_string: "abcd".to_owned(), an_int: 14 };
+///
+/// let mut batch = documents::WriteBatch::new(&session);
+/// batch
+///     .set("tests", "batch_a", &obj)?
+///     .update("tests", "batch_b", &obj, &["an_int"])?
+///     .delete("tests/batch_c");
+/// for result in batch.commit()? {
+///     println!("updated: {:?}", result.update_time);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+pub struct WriteBatch<'a, BEARER> {
+    auth: &'a BEARER,
+    writes: Vec<dto::Write>,
+    serialize_options: SerializeOptions,
+}
+
+impl<'a, BEARER> WriteBatch<'a, BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    /// Create an empty write batch
+    pub fn new(auth: &'a BEARER) -> Self {
+        WriteBatch {
+            auth,
+            writes: vec![],
+            serialize_options: SerializeOptions::default(),
+        }
+    }
+
+    /// Sets the options of the document serialization for all following writes of this batch.
+    /// See [`SerializeOptions`].
+    pub fn serialize_options(&mut self, options: SerializeOptions) -> &mut Self {
+        self.serialize_options = options;
+        self
+    }
+
+    ///
+    /// Write the given document. An existing document will be overwritten.
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+    /// * 'document' The document
+    pub fn set<T>(&mut self, path: &str, document_id: impl AsRef<str>, document: &T) -> Result<&mut Self>
+    where
+        T: Serialize,
+    {
+        let name = document_name(self.auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+        self.writes
+            .push(update_write(name, document, false, self.serialize_options)?);
+        Ok(self)
+    }
+
+    ///
+    /// Update the given fields of an existing document. The commit fails if the document does not exist.
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+    /// * 'document' The document
+    /// * 'field_paths' The fields to update. A field in this list that is not set in the document will be deleted.
+    ///    If the list is empty, all fields of the given document are updated. Keys of map fields are updated individually.
+    ///    See [`field_path`] for field names that need quoting.
+    pub fn update<T>(
+        &mut self,
+        path: &str,
+        document_id: impl AsRef<str>,
+        document: &T,
+        field_paths: &[&str],
+    ) -> Result<&mut Self>
+    where
+        T: Serialize,
+    {
+        let name = document_name(self.auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+        let mut write = update_write(name, document, true, self.serialize_options)?;
+        if !field_paths.is_empty() {
+            write.update_mask = Some(dto::DocumentMask {
+                field_paths: field_paths.iter().map(|f| (*f).to_owned()).collect(),
+            });
+        }
+        self.writes.push(write);
+        Ok(self)
+    }
+
+    ///
+    /// Create the given document. The commit fails if the document already exists.
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+    /// * 'document' The document
+    pub fn create<T>(&mut self, path: &str, document_id: impl AsRef<str>, document: &T) -> Result<&mut Self>
+    where
+        T: Serialize,
+    {
+        let name = document_name(self.auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+        let mut write = update_write(name, document, false, self.serialize_options)?;
+        write.current_document = Some(dto::Precondition {
+            exists: Some(false),
+            ..Default::default()
+        });
+        self.writes.push(write);
+        Ok(self)
+    }
+
+    ///
+    /// Delete the document at the given path.
+    ///
+    /// ## Arguments
+    /// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+    pub fn delete(&mut self, path: &str) -> &mut Self {
+        self.writes.push(dto::Write {
+            delete: Some(document_name(self.auth.project_id(), path)),
+            ..Default::default()
+        });
+        self
+    }
+
+    ///
+    /// Apply the given server side field transforms to a document, without writing any other field.
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+    /// * 'transforms' Pairs of field path and server value, like [`WriteOptions::transforms`]
+    pub fn transform(
+        &mut self,
+        path: &str,
+        document_id: impl AsRef<str>,
+        transforms: &[(String, FieldValue)],
+    ) -> Result<&mut Self> {
+        let name = document_name(self.auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+        self.writes.push(field_transforms_write(name, transforms)?);
+        Ok(self)
+    }
+
+    /// The number of collected writes
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Returns true if no writes have been collected yet
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Commit all collected writes atomically.
+    ///
+    /// Returns one [`dto::WriteResult`] per write in the order the writes have been added.
+    pub fn commit(self) -> Result<Vec<dto::WriteResult>> {
+        let response = commit(self.auth, self.writes, None)?;
+        Ok(response.write_results.unwrap_or_default())
+    }
+
+    /// [Async] Commit all collected writes atomically.
+    ///
+    /// Returns one [`dto::WriteResult`] per write in the order the writes have been added.
+    pub async fn commit_async(self) -> Result<Vec<dto::WriteResult>> {
+        let response = commit_async(self.auth, self.writes, None).await?;
+        Ok(response.write_results.unwrap_or_default())
+    }
+}
+
+/// Creates a write that updates the document with the given absolute name.
+///
+/// If "merge" is set, only the fields of the given document are updated
+/// and the target document must exist. Keys of map fields are merged individually.
+pub(super) fn update_write<T>(
+    name: String,
+    document: &T,
+    merge: bool,
+    serialize_options: SerializeOptions,
+) -> Result<dto::Write>
+where
+    T: Serialize,
+{
+    let mut firebase_document = pod_to_document_with(document, serialize_options)?;
+    firebase_document.name = name;
+
+    let mut write = dto::Write::default();
+    if merge {
+        write.current_document = Some(dto::Precondition {
+            exists: Some(true),
+            ..Default::default()
+        });
+        write.update_mask = Some(dto::DocumentMask {
+            field_paths: firebase_document
+                .fields
+                .as_ref()
+                .map(document_field_paths)
+                .unwrap_or_default(),
+        });
+    }
+    write.update = Some(firebase_document);
+    Ok(write)
+}
+
+/// Commits the given writes atomically, optionally as part of the given transaction.
+///
+/// Commits are not retried, because writes like field transforms are not idempotent.
+pub(super) fn commit(
+    auth: &impl FirebaseAuthBearer,
+    writes: Vec<dto::Write>,
+    transaction: Option<String>,
+) -> Result<dto::CommitResponse> {
+    commit_with(auth, writes, transaction, |_, err| err)
+}
+
+/// Commits like [`commit`]. A Google API error is handed to `map_error` together with its status.
+pub(super) fn commit_with(
+    auth: &impl FirebaseAuthBearer,
+    writes: Vec<dto::Write>,
+    transaction: Option<String>,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
+) -> Result<dto::CommitResponse> {
+    let url = firebase_url_action(auth.project_id(), "commit");
+    let context = transaction.clone().unwrap_or_default();
+    let request = dto::CommitRequest {
+        writes: Some(writes),
+        transaction,
+    };
+
+    let resp = auth
+        .client()
+        .post(&url)
+        .bearer_auth(auth.access_token().to_owned())
+        .json(&request)
+        .send()?;
+
+    let resp = extract_google_api_error_with(resp, || context.clone(), map_error)?;
+
+    Ok(resp.json()?)
+}
+
+/// [Async] Commits the given writes atomically, optionally as part of the given transaction.
+///
+/// Commits are not retried, because writes like field transforms are not idempotent.
+pub(super) async fn commit_async(
+    auth: &impl FirebaseAuthBearer,
+    writes: Vec<dto::Write>,
+    transaction: Option<String>,
+) -> Result<dto::CommitResponse> {
+    commit_with_async(auth, writes, transaction, |_, err| err).await
+}
+
+/// [Async] Commits like [`commit_async`]. A Google API error is handed to `map_error` together with its status.
+pub(super) async fn commit_with_async(
+    auth: &impl FirebaseAuthBearer,
+    writes: Vec<dto::Write>,
+    transaction: Option<String>,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
+) -> Result<dto::CommitResponse> {
+    let url = firebase_url_action(auth.project_id(), "commit");
+    let context = transaction.clone().unwrap_or_default();
+    let request = dto::CommitRequest {
+        writes: Some(writes),
+        transaction,
+    };
+
+    let resp = auth
+        .client_async()
+        .post(&url)
+        .bearer_auth(auth.access_token().to_owned())
+        .json(&request)
+        .send()
+        .await?;
+
+    let resp = extract_google_api_error_with_async(resp, || context.clone(), map_error).await?;
+
+    Ok(resp.json().await?)
+}
@@ -0,0 +1,407 @@
+use super::*;
+use futures::stream::{self, Stream};
+use std::vec::IntoIter;
+
+/// The url of the listCollectionIds endpoint of the given relative document path. An empty path denotes the root.
+#[inline]
+fn collection_ids_url(project_id: &str, parent: &str) -> String {
+    if parent.is_empty() {
+        firebase_url_action(project_id, "listCollectionIds")
+    } else {
+        format!(
+            "{}:listCollectionIds",
+            firebase_url_base(&document_name(project_id, parent))
+        )
+    }
+}
+
+pub(super) fn get_collection_ids(
+    auth: &impl FirebaseAuthBearer,
+    parent: &str,
+    page_token: Option<String>,
+) -> Result<dto::ListCollectionIdsResponse> {
+    let url = collection_ids_url(auth.project_id(), parent);
+    let request = dto::ListCollectionIdsRequest {
+        page_token,
+        ..Default::default()
+    };
+
+    let resp = exp_backoff(
+        || {
+            let resp = auth
+                .client()
+                .post(&url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(&request)
+                .send()
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error(resp, || parent.to_owned()) {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )?;
+
+    let json: dto::ListCollectionIdsResponse = resp.json()?;
+    Ok(json)
+}
+
+pub(super) async fn get_collection_ids_async(
+    auth: &impl FirebaseAuthBearer,
+    parent: &str,
+    page_token: Option<String>,
+) -> Result<dto::ListCollectionIdsResponse> {
+    let url = collection_ids_url(auth.project_id(), parent);
+    let request = dto::ListCollectionIdsRequest {
+        page_token,
+        ..Default::default()
+    };
+
+    let resp = exp_backoff_async(
+        || async {
+            let resp = auth
+                .client_async()
+                .post(&url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(&request)
+                .send()
+                .await
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_async(resp, || parent.to_owned()).await {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+    .await?;
+
+    let json: dto::ListCollectionIdsResponse = resp.json().await?;
+    Ok(json)
+}
+
+/// Fetch a page of documents of the given collection path, including missing documents.
+/// Missing documents do not exist themselves, but have subcollections.
+pub(super) fn get_documents_page(
+    auth: &impl FirebaseAuthBearer,
+    collection_path: &str,
+    page_token: Option<String>,
+) -> Result<dto::ListDocumentsResponse> {
+    let url = firebase_url_base(&document_name(auth.project_id(), collection_path));
+    let params = documents_page_params(page_token);
+    get_new_data(collection_path, &url, auth, &params)
+}
+
+/// [Async] Fetch a page of documents of the given collection path, including missing documents.
+/// See [`get_documents_page`].
+pub(super) async fn get_documents_page_async(
+    auth: &impl FirebaseAuthBearer,
+    collection_path: &str,
+    page_token: Option<String>,
+) -> Result<dto::ListDocumentsResponse> {
+    let url = firebase_url_base(&document_name(auth.project_id(), collection_path));
+    let params = documents_page_params(page_token);
+    get_new_data_async(collection_path, &url, auth, &params).await
+}
+
+/// The query parameters of a page of [`get_documents_page`]
+fn documents_page_params(page_token: Option<String>) -> Vec<(&'static str, String)> {
+    let mut params = ListOptions {
+        show_missing: true,
+        ..Default::default()
+    }
+    .query_params();
+    if let Some(page_token) = page_token {
+        params.push(("pageToken", page_token));
+    }
+    params
+}
+
+///
+/// List the ids of all collections of a document, or of all root collections.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// for collection_id in documents::list_collection_ids(&session, "") {
+///     println!("{}", collection_id?);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'parent' The relative document path, for example "my_collection/document_id". An empty string lists the root collections.
+pub fn list_collection_ids<BEARER>(auth: &BEARER, parent: impl Into<String>) -> CollectionIds<BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    CollectionIds {
+        auth,
+        parent: parent.into(),
+        collection_ids: Vec::new().into_iter(),
+        next_page_token: None,
+        done: false,
+    }
+}
+
+///
+/// [Async] List the ids of all collections of a document, or of all root collections.
+///
+/// Returns a [`Stream`] of collection ids. New pages are fetched lazily, when the ids of the previous page
+/// have been consumed.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'parent' The relative document path, for example "my_collection/document_id". An empty string lists the root collections.
+pub fn list_collection_ids_async<'a, BEARER>(
+    auth: &'a BEARER,
+    parent: impl Into<String>,
+) -> impl Stream<Item = Result<String>> + 'a
+where
+    BEARER: FirebaseAuthBearer,
+{
+    let state = CollectionIdsAsyncState {
+        parent: parent.into(),
+        collection_ids: Vec::new().into_iter(),
+        next_page_token: None,
+        done: false,
+    };
+
+    stream::unfold(state, move |mut state| async move {
+        loop {
+            if let Some(collection_id) = state.collection_ids.next() {
+                return Some((Ok(collection_id), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            match get_collection_ids_async(auth, &state.parent, state.next_page_token.take()).await {
+                Err(e) => {
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+                Ok(v) => {
+                    state.collection_ids = v.collection_ids.unwrap_or_default().into_iter();
+                    state.next_page_token = v.next_page_token;
+                    state.done = state.next_page_token.is_none();
+                }
+            }
+        }
+    })
+}
+
+/// The state of the stream returned by [`list_collection_ids_async`]
+struct CollectionIdsAsyncState {
+    parent: String,
+    collection_ids: IntoIter<String>,
+    next_page_token: Option<String>,
+    done: bool,
+}
+
+/// This type is returned as a result by [`list_collection_ids`].
+/// Use it as an iterator. The paging API is used internally and new pages are fetched lazily.
+pub struct CollectionIds<'a, BEARER> {
+    auth: &'a BEARER,
+    parent: String,
+    collection_ids: IntoIter<String>,
+    next_page_token: Option<String>,
+    done: bool,
+}
+
+impl<'a, BEARER> Iterator for CollectionIds<'a, BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(collection_id) = self.collection_ids.next() {
+                return Some(Ok(collection_id));
+            }
+            if self.done {
+                return None;
+            }
+
+            match get_collection_ids(self.auth, &self.parent, self.next_page_token.take()) {
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+                Ok(v) => {
+                    self.collection_ids = v.collection_ids.unwrap_or_default().into_iter();
+                    self.next_page_token = v.next_page_token;
+                    self.done = self.next_page_token.is_none();
+                }
+            }
+        }
+    }
+}
+
+/// An entry of a document tree, returned by [`walk`]
+#[derive(Debug)]
+pub enum WalkEntry {
+    /// A collection with the given relative path, for example "my_collection/document_id/sub_collection"
+    Collection(String),
+    /// A document, including all its fields
+    Document(dto::Document),
+}
+
+/// A pending step of a [`Walk`]
+enum WalkStep {
+    Collection(String),
+    Documents(String, Option<String>),
+    Document(dto::Document),
+    CollectionIds(String, Option<String>),
+}
+
+///
+/// Visits every collection and document below the given path, depth first.
+///
+/// Documents that do not exist themselves, but have subcollections, are not returned. Their subcollections are visited though.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{self, WalkEntry};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// for entry in documents::walk(&session, "tests") {
+///     match entry? {
+///         WalkEntry::Collection(path) => println!("collection {}", path),
+///         WalkEntry::Document(doc) => println!("document {}", doc.name),
+///     }
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' A collection path like "my_collection", a document path like "my_collection/document_id"
+///   or an empty string for the entire database. A given collection is part of the result, a given document is not.
+pub fn walk<BEARER>(auth: &BEARER, path: impl Into<String>) -> Walk<BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    let path = path.into();
+    let step = if path.is_empty() || path.split('/').count() % 2 == 0 {
+        WalkStep::CollectionIds(path, None)
+    } else {
+        WalkStep::Collection(path)
+    };
+    Walk {
+        auth,
+        steps: vec![step],
+    }
+}
+
+/// This type is returned as a result by [`walk`].
+/// Use it as an iterator. Documents and collection ids are fetched lazily, page by page.
+pub struct Walk<'a, BEARER> {
+    auth: &'a BEARER,
+    steps: Vec<WalkStep>,
+}
+
+impl<'a, BEARER> Iterator for Walk<'a, BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    type Item = Result<WalkEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(step) = self.steps.pop() {
+            match step {
+                WalkStep::Collection(path) => {
+                    self.steps.push(WalkStep::Documents(path.clone(), None));
+                    return Some(Ok(WalkEntry::Collection(path)));
+                }
+                WalkStep::Documents(path, page_token) => {
+                    let response = match get_documents_page(self.auth, &path, page_token) {
+                        Ok(response) => response,
+                        Err(e) => {
+                            self.steps.clear();
+                            return Some(Err(e));
+                        }
+                    };
+                    if response.next_page_token.is_some() {
+                        self.steps.push(WalkStep::Documents(path, response.next_page_token));
+                    }
+                    let documents = response.documents.unwrap_or_default();
+                    self.steps.extend(documents.into_iter().rev().map(WalkStep::Document));
+                }
+                WalkStep::Document(doc) => {
+                    self.steps
+                        .push(WalkStep::CollectionIds(abs_to_rel(&doc.name).to_owned(), None));
+                    // Missing documents have no create time
+                    if doc.create_time.is_some() {
+                        return Some(Ok(WalkEntry::Document(doc)));
+                    }
+                }
+                WalkStep::CollectionIds(parent, page_token) => {
+                    let response = match get_collection_ids(self.auth, &parent, page_token) {
+                        Ok(response) => response,
+                        Err(e) => {
+                            self.steps.clear();
+                            return Some(Err(e));
+                        }
+                    };
+                    if response.next_page_token.is_some() {
+                        self.steps
+                            .push(WalkStep::CollectionIds(parent.clone(), response.next_page_token));
+                    }
+                    let collection_ids = response.collection_ids.unwrap_or_default();
+                    self.steps.extend(collection_ids.into_iter().rev().map(|id| {
+                        if parent.is_empty() {
+                            WalkStep::Collection(id)
+                        } else {
+                            WalkStep::Collection(format!("{}/{}", parent, id))
+                        }
+                    }));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn collection_ids_url_test() {
+    assert_eq!(
+        collection_ids_url("p", ""),
+        "https://firestore.googleapis.com/v1/projects/p/databases/(default)/documents:listCollectionIds"
+    );
+    assert_eq!(
+        collection_ids_url("p", "users/u1"),
+        "https://firestore.googleapis.com/v1/projects/p/databases/(default)/documents/users/u1:listCollectionIds"
+    );
+}
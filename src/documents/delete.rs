@@ -6,40 +6,105 @@ use super::*;
 /// You cannot use this directly with paths from [`list`] and [`query`] document metadata objects.
 /// Those contain an absolute document path. Use [`abs_to_rel`] to convert to a relative path.
 ///
+/// Setting 'fail_if_not_existing' is a shortcut for the precondition [`Precondition::MustExist`], but a missing
+/// document is reported as [`FirebaseError::APIError`] with the code 404. See [`delete_with_precondition`].
+///
 /// ## Arguments
 /// * 'auth' The authentication token
 /// * 'path' The relative collection path and document id, for example "my_collection/document_id"
 /// * 'fail_if_not_existing' If true this method will return an error if the document does not exist.
 pub fn delete(auth: &impl FirebaseAuthBearer, path: &str, fail_if_not_existing: bool) -> Result<()> {
-    let url = firebase_url(auth.project_id(), path);
+    let precondition = if fail_if_not_existing {
+        Some(Precondition::MustExist)
+    } else {
+        None
+    };
+    delete_document(auth, path, precondition, |_, err| err)
+}
 
-    let query_request = dto::Write {
-        current_document: Some(dto::Precondition {
-            exists: match fail_if_not_existing {
-                true => Some(true),
-                false => None,
-            },
-            ..Default::default()
-        }),
-        ..Default::default()
+//#[unstable(feature = "unstable", issue = "1234", reason = "Not yet decided if _async suffix or own module namespace")]
+///
+/// Deletes the document at the given path.
+///
+/// You cannot use this directly with paths from [`list`] and [`query`] document metadata objects.
+/// Those contain an absolute document path. Use [`abs_to_rel`] to convert to a relative path.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+/// * 'fail_if_not_existing' If true this method will return an error if the document does not exist.
+#[cfg(feature = "unstable")]
+pub async fn delete_async(auth: &impl FirebaseAuthBearer, path: &str, fail_if_not_existing: bool) -> Result<()> {
+    let precondition = if fail_if_not_existing {
+        Some(Precondition::MustExist)
+    } else {
+        None
     };
+    delete_document_async(auth, path, precondition, |_, err| err).await
+}
+
+///
+/// Deletes the document at the given path, if the given precondition holds.
+///
+/// Use [`Precondition::UpdateTime`] to only delete a document that has not been modified since it was read or written.
+/// If the precondition does not hold, a [`FirebaseError::FailedPrecondition`] is returned.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+/// * 'precondition' The condition that must hold for the document to be deleted
+pub fn delete_with_precondition(auth: &impl FirebaseAuthBearer, path: &str, precondition: Precondition) -> Result<()> {
+    delete_document(auth, path, Some(precondition), precondition_error)
+}
+
+///
+/// [Async] Deletes the document at the given path, if the given precondition holds.
+///
+/// Use [`Precondition::UpdateTime`] to only delete a document that has not been modified since it was read or written.
+/// If the precondition does not hold, a [`FirebaseError::FailedPrecondition`] is returned.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+/// * 'precondition' The condition that must hold for the document to be deleted
+pub async fn delete_with_precondition_async(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    precondition: Precondition,
+) -> Result<()> {
+    delete_document_async(auth, path, Some(precondition), precondition_error).await
+}
+
+/// Deletes the document at the given path. A Google API error is handed to `map_error` together with its status.
+///
+/// Only unconditional deletes are retried. If the response of a conditional delete got lost,
+/// a retry would fail the precondition, although the document has been deleted.
+fn delete_document(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    precondition: Option<Precondition>,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
+) -> Result<()> {
+    let url = firebase_url_base(&document_name(auth.project_id(), path));
+    let params: Vec<_> = precondition.iter().map(Precondition::to_query_param).collect();
+    let retry = precondition.is_none();
 
     exp_backoff(
         || {
             let resp = auth
                 .client()
                 .delete(&url)
+                .query(&params)
                 .bearer_auth(auth.access_token().to_owned())
-                .json(&query_request)
                 .send()
                 .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
 
             let status = resp.status().as_u16();
 
-            match extract_google_api_error(resp, || path.to_owned()) {
+            match extract_google_api_error_with(resp, || path.to_owned(), &map_error) {
                 Ok(new_resp) => Ok(new_resp),
                 Err(err) => {
-                    if retryable_http_status(status) {
+                    if retry && retryable_http_status(status) {
                         Err(backoff::Error::Transient(err))
                     } else {
                         Err(backoff::Error::Permanent(err))
@@ -50,52 +115,37 @@ pub fn delete(auth: &impl FirebaseAuthBearer, path: &str, fail_if_not_existing:
         FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
     )?;
 
-    Ok({})
+    Ok(())
 }
 
-//#[unstable(feature = "unstable", issue = "1234", reason = "Not yet decided if _async suffix or own module namespace")]
-///
-/// Deletes the document at the given path.
-///
-/// You cannot use this directly with paths from [`list`] and [`query`] document metadata objects.
-/// Those contain an absolute document path. Use [`abs_to_rel`] to convert to a relative path.
-///
-/// ## Arguments
-/// * 'auth' The authentication token
-/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
-/// * 'fail_if_not_existing' If true this method will return an error if the document does not exist.
-#[cfg(feature = "unstable")]
-pub async fn delete_async(auth: &impl FirebaseAuthBearer, path: &str, fail_if_not_existing: bool) -> Result<()> {
-    let url = firebase_url(auth.project_id(), path);
-
-    let query_request = dto::Write {
-        current_document: Some(dto::Precondition {
-            exists: match fail_if_not_existing {
-                true => Some(true),
-                false => None,
-            },
-            ..Default::default()
-        }),
-        ..Default::default()
-    };
+/// [Async] Deletes the document at the given path. See [`delete_document`].
+async fn delete_document_async(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    precondition: Option<Precondition>,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
+) -> Result<()> {
+    let url = firebase_url_base(&document_name(auth.project_id(), path));
+    let params: Vec<_> = precondition.iter().map(Precondition::to_query_param).collect();
+    let retry = precondition.is_none();
 
     exp_backoff_async(
         || async {
             let resp = auth
                 .client_async()
                 .delete(&url)
+                .query(&params)
                 .bearer_auth(auth.access_token().to_owned())
-                .json(&query_request)
                 .send()
                 .await
                 .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
 
             let status = resp.status().as_u16();
 
-            match extract_google_api_error_async(resp, || path.to_owned()).await {
+            match extract_google_api_error_with_async(resp, || path.to_owned(), &map_error).await {
                 Ok(new_resp) => Ok(new_resp),
                 Err(err) => {
-                    if retryable_http_status(status) {
+                    if retry && retryable_http_status(status) {
                         Err(backoff::Error::Transient(err))
                     } else {
                         Err(backoff::Error::Permanent(err))
@@ -107,5 +157,5 @@ pub async fn delete_async(auth: &impl FirebaseAuthBearer, path: &str, fail_if_no
     )
     .await?;
 
-    Ok({})
+    Ok(())
 }
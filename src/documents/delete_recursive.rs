@@ -0,0 +1,370 @@
+use super::*;
+use futures::stream::StreamExt;
+use std::time::{Duration, Instant};
+
+/// The maximum number of deletes per commit
+const DELETE_BATCH_SIZE: usize = 500;
+
+/// The minimum time between two delete commits.
+/// Together with the batch size this keeps the rate at 500 deletes per second, as recommended by Firestore.
+const DELETE_BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The progress of a [`delete_recursive`] or [`delete_collection`] call, or of their async variants.
+/// It is reported after each committed batch.
+#[derive(Clone, Debug, Default)]
+pub struct DeleteProgress {
+    /// The number of deleted documents so far
+    pub deleted_documents: usize,
+    /// The number of committed batches so far
+    pub batches: usize,
+    /// The absolute name of the most recently deleted document
+    pub last_deleted: Option<String>,
+}
+
+/// A pending step of a recursive delete
+enum DeleteStep {
+    /// Visit the subcollections of the document with the given relative path
+    Document(String),
+    /// Delete the document with the given relative path. All its subcollections have been visited already.
+    DeleteDocument(String),
+    /// Visit a page of documents of the collection with the given relative path
+    Collection(String, Option<String>),
+}
+
+/// Deletes documents bottom-up: Subcollections of a document are deleted before the document itself.
+/// A failed delete can therefore be resumed by just starting it again.
+struct RecursiveDelete<'a, BEARER, F> {
+    auth: &'a BEARER,
+    steps: Vec<DeleteStep>,
+    writes: Vec<dto::Write>,
+    progress: DeleteProgress,
+    last_commit: Option<Instant>,
+    on_progress: F,
+}
+
+impl<'a, BEARER, F> RecursiveDelete<'a, BEARER, F>
+where
+    BEARER: FirebaseAuthBearer,
+    F: FnMut(&DeleteProgress),
+{
+    fn new(auth: &'a BEARER, steps: Vec<DeleteStep>, on_progress: F) -> Self {
+        RecursiveDelete {
+            auth,
+            steps,
+            writes: Vec::new(),
+            progress: DeleteProgress::default(),
+            last_commit: None,
+            on_progress,
+        }
+    }
+
+    fn run(mut self) -> Result<DeleteProgress> {
+        self.process_steps()?;
+        self.commit()?;
+        Ok(self.progress)
+    }
+
+    async fn run_async(mut self) -> Result<DeleteProgress> {
+        self.process_steps_async().await?;
+        self.commit_async().await?;
+        Ok(self.progress)
+    }
+
+    /// Processes all pending steps. Deletes of the last, incomplete batch are not committed yet.
+    fn process_steps(&mut self) -> Result<()> {
+        while let Some(step) = self.steps.pop() {
+            match step {
+                DeleteStep::Document(path) => {
+                    self.steps.push(DeleteStep::DeleteDocument(path.clone()));
+                    let mut page_token = None;
+                    loop {
+                        let response = get_collection_ids(self.auth, &path, page_token)?;
+                        page_token = self.push_collections(&path, response);
+                        if page_token.is_none() {
+                            break;
+                        }
+                    }
+                }
+                DeleteStep::Collection(path, page_token) => {
+                    let response = get_documents_page(self.auth, &path, page_token)?;
+                    self.push_documents(path, response);
+                }
+                DeleteStep::DeleteDocument(path) => {
+                    if self.push_delete(&path) {
+                        self.commit()?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// [Async] Processes all pending steps. See [`RecursiveDelete::process_steps`].
+    async fn process_steps_async(&mut self) -> Result<()> {
+        while let Some(step) = self.steps.pop() {
+            match step {
+                DeleteStep::Document(path) => {
+                    self.steps.push(DeleteStep::DeleteDocument(path.clone()));
+                    let mut page_token = None;
+                    loop {
+                        let response = get_collection_ids_async(self.auth, &path, page_token).await?;
+                        page_token = self.push_collections(&path, response);
+                        if page_token.is_none() {
+                            break;
+                        }
+                    }
+                }
+                DeleteStep::Collection(path, page_token) => {
+                    let response = get_documents_page_async(self.auth, &path, page_token).await?;
+                    self.push_documents(path, response);
+                }
+                DeleteStep::DeleteDocument(path) => {
+                    if self.push_delete(&path) {
+                        self.commit_async().await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a step for each collection of the given page. Returns the token of the next page, if any.
+    fn push_collections(&mut self, path: &str, response: dto::ListCollectionIdsResponse) -> Option<String> {
+        for collection_id in response.collection_ids.unwrap_or_default() {
+            self.steps
+                .push(DeleteStep::Collection(format!("{}/{}", path, collection_id), None));
+        }
+        response.next_page_token
+    }
+
+    /// Adds a step for each document of the given page, and one for the next page of the collection
+    fn push_documents(&mut self, path: String, response: dto::ListDocumentsResponse) {
+        if response.next_page_token.is_some() {
+            self.steps.push(DeleteStep::Collection(path, response.next_page_token));
+        }
+        for doc in response.documents.unwrap_or_default() {
+            self.steps.push(DeleteStep::Document(abs_to_rel(&doc.name).to_owned()));
+        }
+    }
+
+    /// Adds the delete of the given document. Returns true if the batch is full and must be committed.
+    fn push_delete(&mut self, path: &str) -> bool {
+        self.writes.push(dto::Write {
+            delete: Some(document_name(self.auth.project_id(), path)),
+            ..Default::default()
+        });
+        self.writes.len() >= DELETE_BATCH_SIZE
+    }
+
+    /// The time to wait before the next commit, to keep at most one commit per [`DELETE_BATCH_INTERVAL`]
+    fn commit_delay(&self) -> Option<Duration> {
+        let elapsed = self.last_commit?.elapsed();
+        if elapsed < DELETE_BATCH_INTERVAL {
+            Some(DELETE_BATCH_INTERVAL - elapsed)
+        } else {
+            None
+        }
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        if self.writes.is_empty() {
+            return Ok(());
+        }
+        if let Some(delay) = self.commit_delay() {
+            std::thread::sleep(delay);
+        }
+
+        let writes = std::mem::take(&mut self.writes);
+        // Deletes without preconditions are idempotent and can safely be retried
+        exp_backoff(
+            || match commit(self.auth, writes.clone(), None) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(retryable_commit_error(err)),
+            },
+            FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+        )?;
+        self.committed(&writes);
+        Ok(())
+    }
+
+    /// [Async] Commits the pending deletes. See [`RecursiveDelete::commit`].
+    async fn commit_async(&mut self) -> Result<()> {
+        if self.writes.is_empty() {
+            return Ok(());
+        }
+        if let Some(delay) = self.commit_delay() {
+            tokio::time::delay_for(delay).await;
+        }
+
+        let writes = std::mem::take(&mut self.writes);
+        let auth = self.auth;
+        // Deletes without preconditions are idempotent and can safely be retried
+        exp_backoff_async(
+            || async {
+                match commit_async(auth, writes.clone(), None).await {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(retryable_commit_error(err)),
+                }
+            },
+            FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+        )
+        .await?;
+        self.committed(&writes);
+        Ok(())
+    }
+
+    /// Updates and reports the progress after the given writes have been committed
+    fn committed(&mut self, writes: &[dto::Write]) {
+        self.last_commit = Some(Instant::now());
+        self.progress.deleted_documents += writes.len();
+        self.progress.batches += 1;
+        self.progress.last_deleted = writes.last().and_then(|w| w.delete.clone());
+        (self.on_progress)(&self.progress);
+    }
+}
+
+/// Marks errors of a delete commit with a retryable http status as transient
+fn retryable_commit_error(err: FirebaseError) -> backoff::Error<FirebaseError> {
+    match err {
+        FirebaseError::APIError(code, msg, ctx) if retryable_http_status(code as u16) => {
+            backoff::Error::Transient(FirebaseError::APIError(code, msg, ctx))
+        }
+        err => backoff::Error::Permanent(err),
+    }
+}
+
+///
+/// Deletes the document at the given path together with all its subcollections and their documents.
+///
+/// Documents are deleted bottom-up in batches of 500, with at most one batch per second.
+/// If the deletion fails midway, just call this method again. Already deleted documents are gone
+/// and the deletion continues with the remaining ones.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let progress = documents::delete_recursive(&session, "tests/non_existing", |progress| {
+///     println!("{} documents deleted", progress.deleted_documents);
+/// })?;
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+/// * 'on_progress' Called after each committed batch
+pub fn delete_recursive(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    on_progress: impl FnMut(&DeleteProgress),
+) -> Result<DeleteProgress> {
+    RecursiveDelete::new(auth, vec![DeleteStep::Document(path.to_owned())], on_progress).run()
+}
+
+///
+/// [Async] Deletes the document at the given path together with all its subcollections and their documents.
+/// See [`delete_recursive`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+/// * 'on_progress' Called after each committed batch
+pub async fn delete_recursive_async(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    on_progress: impl FnMut(&DeleteProgress),
+) -> Result<DeleteProgress> {
+    RecursiveDelete::new(auth, vec![DeleteStep::Document(path.to_owned())], on_progress)
+        .run_async()
+        .await
+}
+
+///
+/// Deletes all documents of a collection together with all their subcollections.
+///
+/// If a filter is given, only the matching documents (and their subcollections) are deleted.
+/// See [`delete_recursive`] for details.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection' The collection path, for example "my_collection" or "a/nested/collection"
+/// * 'filter' An optional filter, for example `Some(field("expired").eq(true)?)`
+/// * 'on_progress' Called after each committed batch
+pub fn delete_collection(
+    auth: &impl FirebaseAuthBearer,
+    collection: &str,
+    filter: Option<QueryFilter>,
+    on_progress: impl FnMut(&DeleteProgress),
+) -> Result<DeleteProgress> {
+    let mut delete = RecursiveDelete::new(auth, Vec::new(), on_progress);
+    let filter = match filter {
+        None => {
+            delete.steps.push(DeleteStep::Collection(collection.to_owned(), None));
+            return delete.run();
+        }
+        Some(filter) => filter,
+    };
+
+    // The matching documents are deleted page by page. Each page continues after the last document of the
+    // previous one, so deleting the documents of a page does not affect the following pages.
+    let query = QueryBuilder::collection(collection).filter(filter).select(&[]);
+    let mut documents = query.paginate(auth, DELETE_BATCH_SIZE as u32);
+    loop {
+        for doc in documents.by_ref().take(DELETE_BATCH_SIZE) {
+            delete
+                .steps
+                .push(DeleteStep::Document(abs_to_rel(&doc?.name).to_owned()));
+        }
+        if delete.steps.is_empty() {
+            return delete.run();
+        }
+        delete.process_steps()?;
+    }
+}
+
+///
+/// [Async] Deletes all documents of a collection together with all their subcollections.
+/// See [`delete_collection`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection' The collection path, for example "my_collection" or "a/nested/collection"
+/// * 'filter' An optional filter, for example `Some(field("expired").eq(true)?)`
+/// * 'on_progress' Called after each committed batch
+pub async fn delete_collection_async(
+    auth: &impl FirebaseAuthBearer,
+    collection: &str,
+    filter: Option<QueryFilter>,
+    on_progress: impl FnMut(&DeleteProgress),
+) -> Result<DeleteProgress> {
+    let mut delete = RecursiveDelete::new(auth, Vec::new(), on_progress);
+    let filter = match filter {
+        None => {
+            delete.steps.push(DeleteStep::Collection(collection.to_owned(), None));
+            return delete.run_async().await;
+        }
+        Some(filter) => filter,
+    };
+
+    // The matching documents are deleted page by page, see delete_collection
+    let query = QueryBuilder::collection(collection).filter(filter).select(&[]);
+    let mut documents = Box::pin(query.paginate_async(auth, DELETE_BATCH_SIZE as u32));
+    loop {
+        let mut page = (&mut documents).take(DELETE_BATCH_SIZE);
+        while let Some(doc) = page.next().await {
+            delete
+                .steps
+                .push(DeleteStep::Document(abs_to_rel(&doc?.name).to_owned()));
+        }
+        if delete.steps.is_empty() {
+            return delete.run_async().await;
+        }
+        delete.process_steps_async().await?;
+    }
+}
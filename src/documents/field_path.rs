@@ -0,0 +1,107 @@
+use super::*;
+use std::collections::HashMap;
+
+/// Returns true if the given field name can be used in a field path without quoting
+fn is_simple_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+///
+/// Quotes a single field name for the use in a field path, if necessary.
+///
+/// Field names that do not only consist of letters, digits and underscores or that start with a digit
+/// are quoted with backticks, for example "some.key" becomes "`some.key`".
+pub fn quote_field_name(name: &str) -> String {
+    if is_simple_field_name(name) {
+        return name.to_owned();
+    }
+    format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+}
+
+///
+/// Creates a field path of the given field names, for example to refer to a key of a map field.
+/// Each field name is quoted if necessary, see [`quote_field_name`].
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::field_path;
+///
+/// assert_eq!(field_path(&["a_map", "some key"]), "a_map.`some key`");
+/// ```
+pub fn field_path(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| quote_field_name(name))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+///
+/// Splits a field path into its field names. This is the reverse of [`field_path`].
+///
+/// Names quoted with backticks may contain dots, for example "a_map.`some.key`" is split into "a_map" and "some.key".
+pub fn split_field_path(path: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut name = String::new();
+    let mut quoted = false;
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => quoted = !quoted,
+            '\\' if quoted => name.extend(chars.next()),
+            '.' if !quoted => names.push(std::mem::take(&mut name)),
+            c => name.push(c),
+        }
+    }
+    names.push(name);
+    names
+}
+
+/// Collects the paths of all leaf fields of the given document fields.
+/// Map fields are descended into, so that merging only updates the contained keys and not the entire map.
+pub(super) fn document_field_paths(fields: &HashMap<String, dto::Value>) -> Vec<String> {
+    let mut field_paths = Vec::new();
+    collect_field_paths("", fields, &mut field_paths);
+    field_paths
+}
+
+fn collect_field_paths(prefix: &str, fields: &HashMap<String, dto::Value>, field_paths: &mut Vec<String>) {
+    for (name, value) in fields {
+        let path = if prefix.is_empty() {
+            quote_field_name(name)
+        } else {
+            format!("{}.{}", prefix, quote_field_name(name))
+        };
+        match value.map_value.as_ref() {
+            Some(map) if !map.fields.is_empty() => collect_field_paths(&path, &map.fields, field_paths),
+            _ => field_paths.push(path),
+        }
+    }
+}
+
+#[test]
+fn field_path_test() {
+    assert_eq!(quote_field_name("a_string"), "a_string");
+    assert_eq!(quote_field_name("000"), "`000`");
+    assert_eq!(quote_field_name("a.b"), "`a.b`");
+    assert_eq!(quote_field_name("a`b"), "`a\\`b`");
+    assert_eq!(field_path(&["a_map", "some key", "x"]), "a_map.`some key`.x");
+    assert_eq!(split_field_path("a_map.`some.key`.x"), vec!["a_map", "some.key", "x"]);
+    assert_eq!(split_field_path(&field_path(&["a`b", "c\\d"])), vec!["a`b", "c\\d"]);
+    assert_eq!(split_field_path("a"), vec!["a"]);
+
+    let document = pod_to_document(&serde_json::json!({
+        "a": 1,
+        "a_map": { "some.key": { "b": true }, "c": "d" },
+        "empty": {}
+    }))
+    .unwrap();
+    let mut field_paths = document_field_paths(document.fields.as_ref().unwrap());
+    field_paths.sort();
+    assert_eq!(field_paths, vec!["a", "a_map.`some.key`.b", "a_map.c", "empty"]);
+}
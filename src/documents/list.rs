@@ -1,4 +1,59 @@
 use super::*;
+use futures::stream::{self, Stream, StreamExt};
+use std::vec::IntoIter;
+
+/// Options for listing documents with [`list_with_options`] and [`list_with_options_async`].
+#[derive(Clone, Debug, Default)]
+pub struct ListOptions {
+    /// Only return the given fields of each document. Nested fields are separated by a dot, for example "a_map.a".
+    pub mask: Option<Vec<String>>,
+    /// List the documents within the transaction with the given id, see [`Transaction::id`].
+    pub transaction: Option<String>,
+    /// List the documents as they were at the given time. Use the same read time for multiple requests
+    /// to get a consistent snapshot. This can not be combined with a transaction.
+    pub read_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// The maximum number of documents per page. The server chooses the page size if this is not set.
+    /// Page sizes above `i32::max_value()` are clamped.
+    pub page_size: Option<u32>,
+    /// Orders the documents by the given fields. For example ("field_1", true) for order by field_1 ascendingly.
+    /// Documents are ordered by their id if this is not set.
+    pub order_by: Option<Vec<(String, bool)>>,
+    /// Also list missing documents. Missing documents do not exist themselves, but have subcollections.
+    /// They have no fields and no create or update time.
+    pub show_missing: bool,
+}
+
+impl ListOptions {
+    /// The options as url query parameters
+    pub(super) fn query_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = ReadOptions {
+            mask: self.mask.clone(),
+            transaction: self.transaction.clone(),
+            read_time: self.read_time,
+        }
+        .query_params();
+        if let Some(page_size) = self.page_size {
+            params.push(("pageSize", clamp_to_i32(page_size).to_string()));
+        }
+        if let Some(order_by) = &self.order_by {
+            let order_by: Vec<String> = order_by
+                .iter()
+                .map(|(field_path, ascending)| {
+                    if *ascending {
+                        field_path.clone()
+                    } else {
+                        format!("{} desc", field_path)
+                    }
+                })
+                .collect();
+            params.push(("orderBy", order_by.join(",")));
+        }
+        if self.show_missing {
+            params.push(("showMissing", "true".to_owned()));
+        }
+        params
+    }
+}
 
 /// List all documents of a given collection.
 ///
@@ -33,13 +88,57 @@ use super::*;
 /// * 'auth' The authentication token
 /// * 'collection_id' The document path / collection; For example "my_collection" or "a/nested/collection"
 pub fn list<T, BEARER>(auth: &BEARER, collection_id: impl Into<String>) -> List<T, BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    list_with_options(auth, collection_id, ListOptions::default())
+}
+
+///
+/// List all documents of a given collection, with the given list options.
+///
+/// Use a field mask to only fetch some fields and deserialize them into a small projection struct.
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// /// Only some fields of the documents
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoPartialDTO { an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let options = documents::ListOptions { mask: Some(vec!["an_int".to_owned()]), ..Default::default() };
+/// let values: documents::List<DemoPartialDTO, _> = documents::list_with_options(&session, "tests", options);
+/// for doc_result in values {
+///     let (doc, _metadata) = doc_result?;
+///     println!("{:?}", doc);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection_id' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'options' A field mask, a transaction or a read time, the page size, an order or whether to list missing documents
+pub fn list_with_options<T, BEARER>(
+    auth: &BEARER,
+    collection_id: impl Into<String>,
+    options: ListOptions,
+) -> List<T, BEARER>
 where
     BEARER: FirebaseAuthBearer,
 {
     let collection_id = collection_id.into();
     List {
-        url: firebase_url(auth.project_id(), &collection_id),
+        url: firebase_url_base(&document_name(auth.project_id(), &collection_id)),
         auth,
+        params: options.query_params(),
         next_page_token: None,
         documents: vec![],
         current: 0,
@@ -49,17 +148,202 @@ where
     }
 }
 
+///
+/// [Async] List all documents of a given collection.
+///
+/// Returns a [`Stream`] of documents. New pages are fetched lazily, when the documents of the previous page
+/// have been consumed.
+///
+/// Example:
+/// ```no_run
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// use futures::StreamExt;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+/// # let mut rt = tokio::runtime::Runtime::new()?;
+///
+/// rt.block_on(async {
+///     let values = documents::list_async::<DemoDTO, _>(&session, "tests");
+///     futures::pin_mut!(values);
+///     while let Some(doc_result) = values.next().await {
+///         let (doc, _metadata) = doc_result?;
+///         println!("{:?}", doc);
+///     }
+///     Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// })?;
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection_id' The document path / collection; For example "my_collection" or "a/nested/collection"
+pub fn list_async<'a, T, BEARER>(
+    auth: &'a BEARER,
+    collection_id: impl Into<String>,
+) -> impl Stream<Item = Result<(T, dto::Document)>> + 'a
+where
+    for<'b> T: Deserialize<'b>,
+    T: 'a,
+    BEARER: FirebaseAuthBearer,
+{
+    list_with_options_async(auth, collection_id, ListOptions::default())
+}
+
+///
+/// [Async] List all documents of a given collection, with the given list options.
+/// See [`list_async`] and [`list_with_options`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection_id' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'options' A field mask, a transaction or a read time, the page size, an order or whether to list missing documents
+pub fn list_with_options_async<'a, T, BEARER>(
+    auth: &'a BEARER,
+    collection_id: impl Into<String>,
+    options: ListOptions,
+) -> impl Stream<Item = Result<(T, dto::Document)>> + 'a
+where
+    for<'b> T: Deserialize<'b>,
+    T: 'a,
+    BEARER: FirebaseAuthBearer,
+{
+    list_documents_async(auth, collection_id.into(), options).map(|doc| doc.and_then(document_with_metadata))
+}
+
+///
+/// [Async] List all documents of a given collection as [`DocumentSnapshot`]s, with parsed create and update times.
+/// See [`list_async`] and [`List::snapshots`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection_id' The document path / collection; For example "my_collection" or "a/nested/collection"
+pub fn list_snapshots_async<'a, T, BEARER>(
+    auth: &'a BEARER,
+    collection_id: impl Into<String>,
+) -> impl Stream<Item = Result<DocumentSnapshot<T>>> + 'a
+where
+    for<'b> T: Deserialize<'b>,
+    T: 'a,
+    BEARER: FirebaseAuthBearer,
+{
+    list_snapshots_with_options_async(auth, collection_id, ListOptions::default())
+}
+
+///
+/// [Async] List all documents of a given collection as [`DocumentSnapshot`]s, with the given list options.
+/// See [`list_snapshots_async`] and [`list_with_options`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collection_id' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'options' A field mask, a transaction or a read time, the page size, an order or whether to list missing documents
+pub fn list_snapshots_with_options_async<'a, T, BEARER>(
+    auth: &'a BEARER,
+    collection_id: impl Into<String>,
+    options: ListOptions,
+) -> impl Stream<Item = Result<DocumentSnapshot<T>>> + 'a
+where
+    for<'b> T: Deserialize<'b>,
+    T: 'a,
+    BEARER: FirebaseAuthBearer,
+{
+    list_documents_async(auth, collection_id.into(), options)
+        .map(|doc| doc.and_then(|doc| DocumentSnapshot::from_document(&doc)))
+}
+
+/// [Async] The raw documents of a collection. New pages are fetched lazily.
+fn list_documents_async<'a, BEARER>(
+    auth: &'a BEARER,
+    collection_id: String,
+    options: ListOptions,
+) -> impl Stream<Item = Result<dto::Document>> + 'a
+where
+    BEARER: FirebaseAuthBearer,
+{
+    let state = ListAsyncState {
+        url: firebase_url_base(&document_name(auth.project_id(), &collection_id)),
+        params: options.query_params(),
+        collection_id,
+        documents: Vec::new().into_iter(),
+        next_page_token: None,
+        done: false,
+    };
+
+    stream::unfold(state, move |mut state| async move {
+        loop {
+            if let Some(doc) = state.documents.next() {
+                return Some((Ok(doc), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            let mut params = state.params.clone();
+            if let Some(next_page_token) = state.next_page_token.take() {
+                params.push(("pageToken", next_page_token));
+            }
+            match get_new_data_async(&state.collection_id, &state.url, auth, &params).await {
+                Err(e) => {
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+                Ok(v) => {
+                    state.documents = v.documents.unwrap_or_default().into_iter();
+                    state.next_page_token = v.next_page_token;
+                    state.done = state.next_page_token.is_none();
+                }
+            }
+        }
+    })
+}
+
+/// The state of the stream returned by [`list_documents_async`]
+struct ListAsyncState {
+    url: String,
+    params: Vec<(&'static str, String)>,
+    collection_id: String,
+    documents: IntoIter<dto::Document>,
+    next_page_token: Option<String>,
+    done: bool,
+}
+
+/// Deserializes the given document and returns it together with its metadata, without the fields
+fn document_with_metadata<T>(doc: dto::Document) -> Result<(T, dto::Document)>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let pod = document_to_pod(&doc)?;
+    Ok((
+        pod,
+        dto::Document {
+            update_time: doc.update_time,
+            create_time: doc.create_time,
+            name: doc.name,
+            fields: None,
+        },
+    ))
+}
+
 #[inline]
-fn get_new_data<'a>(
+pub(super) fn get_new_data<'a>(
     collection_id: &str,
     url: &str,
     auth: &'a impl FirebaseAuthBearer,
+    params: &[(&str, String)],
 ) -> Result<dto::ListDocumentsResponse> {
     let resp = exp_backoff(
         || {
             let resp = auth
                 .client()
                 .get(url)
+                .query(params)
                 .bearer_auth(auth.access_token().to_owned())
                 .send()
                 .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
@@ -84,6 +368,44 @@ fn get_new_data<'a>(
     Ok(json)
 }
 
+pub(super) async fn get_new_data_async(
+    collection_id: &str,
+    url: &str,
+    auth: &impl FirebaseAuthBearer,
+    params: &[(&str, String)],
+) -> Result<dto::ListDocumentsResponse> {
+    let resp = exp_backoff_async(
+        || async {
+            let resp = auth
+                .client_async()
+                .get(url)
+                .query(params)
+                .bearer_auth(auth.access_token().to_owned())
+                .send()
+                .await
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_async(resp, || collection_id.to_owned()).await {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+    .await?;
+
+    let json: dto::ListDocumentsResponse = resp.json().await?;
+    Ok(json)
+}
+
 /// This type is returned as a result by [`list`].
 /// Use it as an iterator. The paging API is used internally and new pages are fetched lazily.
 ///
@@ -96,64 +418,120 @@ pub struct List<'a, T, BEARER> {
     current: usize,
     done: bool,
     url: String,
+    params: Vec<(&'static str, String)>,
     collection_id: String,
     phantom: std::marker::PhantomData<T>,
 }
 
-impl<'a, T, BEARER> Iterator for List<'a, T, BEARER>
+impl<'a, T, BEARER> List<'a, T, BEARER>
 where
     for<'b> T: Deserialize<'b>,
     BEARER: FirebaseAuthBearer,
 {
-    type Item = Result<(T, dto::Document)>;
+    ///
+    /// Turns this list into an iterator of [`DocumentSnapshot`]s, with parsed create and update times.
+    ///
+    /// Example:
+    /// ```rust
+    /// # use serde::{Serialize, Deserialize};
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct DemoDTO { a_string: String, an_int: u32, }
+    ///
+    /// use firestore_db_and_auth::documents;
+    /// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+    ///
+    /// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+    ///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+    /// # let session = ServiceSession::new(credentials)?;
+    ///
+    /// let values: documents::List<DemoDTO, _> = documents::list(&session, "tests");
+    /// for snapshot in values.snapshots() {
+    ///     let snapshot = snapshot?;
+    ///     println!("{} created at {:?}", snapshot.path(), snapshot.create_time);
+    /// }
+    /// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+    /// ```
+    pub fn snapshots(mut self) -> impl Iterator<Item = Result<DocumentSnapshot<T>>> + 'a
+    where
+        T: 'a,
+    {
+        std::iter::from_fn(move || {
+            Some(
+                self.next_document()?
+                    .and_then(|doc| DocumentSnapshot::from_document(&doc)),
+            )
+        })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.done {
-            return None;
-        }
+    /// Returns the next raw document and fetches a new page if necessary
+    fn next_document(&mut self) -> Option<Result<dto::Document>> {
+        while self.documents.len() <= self.current {
+            if self.done {
+                return None;
+            }
 
-        if self.documents.len() <= self.current {
-            let url = match &self.next_page_token {
-                Some(next_page_token) => format!("{}pageToken={}", self.url, next_page_token),
-                None => self.url.clone(),
-            };
+            let mut params = self.params.clone();
+            if let Some(next_page_token) = self.next_page_token.take() {
+                params.push(("pageToken", next_page_token));
+            }
 
-            let result = get_new_data(&self.collection_id, &url, self.auth);
-            match result {
+            match get_new_data(&self.collection_id, &self.url, self.auth, &params) {
                 Err(e) => {
                     self.done = true;
                     return Some(Err(e));
                 }
-                Ok(v) => match v.documents {
-                    None => return None,
-                    Some(documents) => {
-                        self.documents = documents;
-                        self.current = 0;
-                        self.next_page_token = v.next_page_token;
-                    }
-                },
+                Ok(v) => {
+                    // A page can be empty, but still have a successor
+                    self.documents = v.documents.unwrap_or_default();
+                    self.current = 0;
+                    self.next_page_token = v.next_page_token;
+                    self.done = self.next_page_token.is_none();
+                }
             };
         }
 
-        let doc = self.documents.get(self.current).unwrap();
-
+        let doc = std::mem::take(&mut self.documents[self.current]);
         self.current += 1;
-        if self.documents.len() <= self.current && self.next_page_token.is_none() {
-            self.done = true;
-        }
 
-        let result = document_to_pod(&doc);
-        match result {
-            Err(e) => Some(Err(e)),
-            Ok(pod) => Some(Ok((
-                pod,
-                dto::Document {
-                    update_time: doc.update_time.clone(),
-                    create_time: doc.create_time.clone(),
-                    name: doc.name.clone(),
-                    fields: None,
-                },
-            ))),
-        }
+        Some(Ok(doc))
     }
 }
+
+impl<'a, T, BEARER> Iterator for List<'a, T, BEARER>
+where
+    for<'b> T: Deserialize<'b>,
+    BEARER: FirebaseAuthBearer,
+{
+    type Item = Result<(T, dto::Document)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_document()?.and_then(document_with_metadata))
+    }
+}
+
+#[test]
+fn list_options_test() {
+    let options = ListOptions {
+        mask: Some(vec!["a".to_owned()]),
+        page_size: Some(50),
+        order_by: Some(vec![("priority".to_owned(), false), ("a_map.`000`".to_owned(), true)]),
+        show_missing: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        options.query_params(),
+        vec![
+            ("mask.fieldPaths", "a".to_owned()),
+            ("pageSize", "50".to_owned()),
+            ("orderBy", "priority desc,a_map.`000`".to_owned()),
+            ("showMissing", "true".to_owned()),
+        ]
+    );
+    assert!(ListOptions::default().query_params().is_empty());
+
+    let options = ListOptions {
+        page_size: Some(u32::max_value()),
+        ..Default::default()
+    };
+    assert_eq!(options.query_params(), vec![("pageSize", i32::max_value().to_string())]);
+}
@@ -0,0 +1,921 @@
+use super::*;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use std::collections::{HashSet, VecDeque};
+use std::io::Read;
+use std::time::Duration;
+
+/// The id of the one and only target of a listen stream
+const LISTEN_TARGET_ID: i32 = 1;
+
+/// The Listen stream is not offered via REST. It is consumed via the WebChannel protocol, like the Firebase web SDKs do.
+const LISTEN_CHANNEL_URL: &str = "https://firestore.googleapis.com/google.firestore.v1.Firestore/Listen/channel";
+
+/// The WebChannel protocol version
+const WEBCHANNEL_VERSION: &str = "8";
+
+/// The WebChannel client version that the Firebase web SDKs report
+const WEBCHANNEL_CLIENT_VERSION: &str = "22";
+
+/// The maximum delay between two reconnects
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The number of consecutive failed connection attempts after which a listen stream ends with the last error
+const MAX_FAILED_CONNECTIONS: u32 = 10;
+
+/// The documents to listen to. See [`listen`].
+#[derive(Clone, Debug)]
+pub enum ListenTarget {
+    /// The documents with the given relative paths, for example "my_collection/document_id"
+    Documents(Vec<String>),
+    /// All documents that match the given query
+    Query {
+        /// The relative path of the parent document, for example "my_collection/document_id".
+        /// Leave this empty for queries on root collections.
+        parent: String,
+        /// The query
+        query: dto::StructuredQuery,
+    },
+}
+
+impl ListenTarget {
+    /// Listen to the document with the given relative path, for example "my_collection/document_id"
+    pub fn document(path: impl Into<String>) -> Self {
+        ListenTarget::Documents(vec![path.into()])
+    }
+
+    /// Listen to all documents that match the given query on a root collection
+    pub fn query(query: dto::StructuredQuery) -> Self {
+        ListenTarget::Query {
+            parent: String::new(),
+            query,
+        }
+    }
+
+    fn to_target(&self, project_id: &str) -> dto::Target {
+        let mut target = dto::Target {
+            target_id: Some(LISTEN_TARGET_ID),
+            ..Default::default()
+        };
+        match self {
+            ListenTarget::Documents(paths) => {
+                target.documents = Some(dto::DocumentsTarget {
+                    documents: Some(paths.iter().map(|path| document_name(project_id, path)).collect()),
+                });
+            }
+            ListenTarget::Query { parent, query } => {
+                let parent = if parent.is_empty() {
+                    format!("projects/{}/databases/(default)/documents", project_id)
+                } else {
+                    document_name(project_id, parent)
+                };
+                target.query = Some(dto::QueryTarget {
+                    parent: Some(parent),
+                    structured_query: Some(query.clone()),
+                });
+            }
+        }
+        target
+    }
+}
+
+/// A change of the listened documents. Returned by [`listen`] and [`listen_async`].
+#[derive(Debug)]
+pub enum ListenEvent<T> {
+    /// A document has been added to the target. All initially matching documents are reported via this event.
+    Added(DocumentSnapshot<T>),
+    /// A document of the target has been modified.
+    Modified(DocumentSnapshot<T>),
+    /// A document has been deleted or does not match the target anymore. Contains the absolute document name.
+    Removed(String),
+    /// All changes up to the given read time have been reported.
+    /// This is the case after all initially matching documents have been reported
+    /// and after every following set of changes.
+    Synced(DateTime<Utc>),
+}
+
+/// Splits streamed WebChannel frames into the entries of their arrays, without waiting for a frame to be complete.
+///
+/// A frame is a length prefix followed by a json array, for example `12\n[[1,["noop"]]]`.
+/// The length prefixes are skipped, because they count UTF-16 code units instead of bytes.
+#[derive(Default)]
+struct JsonArraySplitter {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    current: Vec<u8>,
+}
+
+impl JsonArraySplitter {
+    /// Feed the next bytes of the stream and return all elements that are complete now
+    fn push(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut elements = Vec::new();
+        for &b in bytes {
+            if self.depth >= 2 {
+                self.current.push(b);
+            }
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'[' | b'{' => {
+                    self.depth += 1;
+                    if self.depth == 2 {
+                        self.current.push(b);
+                    }
+                }
+                b']' | b'}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 1 {
+                        elements.push(std::mem::take(&mut self.current));
+                    }
+                }
+                _ => {}
+            }
+        }
+        elements
+    }
+}
+
+/// Url parameters or form fields of a WebChannel request
+type Params = Vec<(&'static str, String)>;
+
+/// A WebChannel session, opened by the handshake
+struct ChannelSession {
+    /// The session id
+    sid: String,
+    /// The http session id, which is sent as "gsessionid" parameter
+    http_session_id: Option<String>,
+}
+
+/// The state of a listen stream that is shared by the blocking and async variant
+struct ListenState<T> {
+    target: dto::Target,
+    database: String,
+    splitter: JsonArraySplitter,
+    events: VecDeque<Result<ListenEvent<T>>>,
+    known_documents: HashSet<String>,
+    /// The documents that were known when the target was reset. Those that are not sent again have been removed.
+    reset_documents: HashSet<String>,
+    session: Option<ChannelSession>,
+    /// The id of the last received WebChannel array. The server continues after it.
+    last_array_id: u64,
+    /// Whether anything has been received since the last (re)connect
+    received: bool,
+    /// The number of consecutive connections that failed or closed without receiving anything
+    failures: u32,
+    /// The number of consecutive connection attempts that failed
+    failed_connections: u32,
+    done: bool,
+}
+
+impl<T> ListenState<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    fn new(target: dto::Target, project_id: &str) -> Self {
+        ListenState {
+            target,
+            database: format!("projects/{}/databases/(default)", project_id),
+            splitter: JsonArraySplitter::default(),
+            events: VecDeque::new(),
+            known_documents: HashSet::new(),
+            reset_documents: HashSet::new(),
+            session: None,
+            last_array_id: 0,
+            received: false,
+            failures: 0,
+            failed_connections: 0,
+            done: false,
+        }
+    }
+
+    /// The url parameters and the form body of the handshake, which opens a new session with the listen request.
+    /// If the stream has been interrupted before, the last resume token is used.
+    ///
+    /// The access token is sent as http header of the handshake, see [`open_session`]. Unlike the web SDKs,
+    /// it is never passed via the "$httpHeaders" url parameter, so that it cannot leak via logged urls.
+    fn handshake_request(&self) -> Result<(Params, Params)> {
+        let request = dto::ListenRequest {
+            database: Some(self.database.clone()),
+            add_target: Some(self.target.clone()),
+            ..Default::default()
+        };
+        let params = vec![
+            ("database", self.database.clone()),
+            ("VER", WEBCHANNEL_VERSION.to_owned()),
+            ("RID", (random_id() % 100_000).to_string()),
+            ("CVER", WEBCHANNEL_CLIENT_VERSION.to_owned()),
+            ("X-HTTP-Session-Id", "gsessionid".to_owned()),
+            ("zx", random_id().to_string()),
+            ("t", "1".to_owned()),
+        ];
+        let body = vec![
+            ("count", "1".to_owned()),
+            ("ofs", "0".to_owned()),
+            ("req0___data__", serde_json::to_string(&request)?),
+        ];
+        Ok((params, body))
+    }
+
+    /// Processes the response of the handshake. Events that are part of it are queued.
+    fn open_session(&mut self, body: &[u8], http_session_id: Option<String>) -> Result<()> {
+        self.session = None;
+        self.last_array_id = 0;
+        self.splitter = JsonArraySplitter::default();
+        self.push_bytes(body);
+        match self.session.as_mut() {
+            Some(session) => {
+                session.http_session_id = http_session_id;
+                Ok(())
+            }
+            None => Err(FirebaseError::Generic(
+                "The listen channel handshake did not return a session",
+            )),
+        }
+    }
+
+    /// The url parameters of the back channel, the long-lived request that streams the responses of a session
+    fn back_channel_request(&mut self) -> Params {
+        // A new connection starts with a fresh stream
+        self.splitter = JsonArraySplitter::default();
+        let mut params = vec![("database", self.database.clone())];
+        if let Some(session) = self.session.as_ref() {
+            if let Some(http_session_id) = session.http_session_id.as_ref() {
+                params.push(("gsessionid", http_session_id.clone()));
+            }
+            params.push(("SID", session.sid.clone()));
+        }
+        params.extend(vec![
+            ("VER", WEBCHANNEL_VERSION.to_owned()),
+            ("RID", "rpc".to_owned()),
+            ("AID", self.last_array_id.to_string()),
+            ("CI", "0".to_owned()),
+            ("TYPE", "xmlhttp".to_owned()),
+            ("zx", random_id().to_string()),
+            ("t", "1".to_owned()),
+        ]);
+        params
+    }
+
+    /// The connection has been closed. It will be re-established with the last resume token.
+    fn connection_closed(&mut self) {
+        if self.received {
+            self.failures = 0;
+        } else {
+            self.failures += 1;
+        }
+        self.received = false;
+    }
+
+    /// The back channel has been opened
+    fn connection_opened(&mut self) {
+        self.failed_connections = 0;
+    }
+
+    /// The connection could not be established. A new session will be opened.
+    /// Returns the error once too many attempts in a row have failed, which ends the stream.
+    fn connection_failed(&mut self, error: FirebaseError) -> Option<FirebaseError> {
+        self.session = None;
+        self.received = false;
+        self.failures += 1;
+        self.failed_connections += 1;
+        if self.done || self.failed_connections >= MAX_FAILED_CONNECTIONS {
+            self.done = true;
+            return Some(error);
+        }
+        None
+    }
+
+    /// The delay before the next connection attempt. It doubles with each failed attempt.
+    fn reconnect_delay(&self) -> Duration {
+        if self.failures == 0 {
+            return Duration::from_secs(0);
+        }
+        let delay = Duration::from_millis(500) * 2u32.pow(self.failures.min(7) - 1);
+        delay.min(MAX_RECONNECT_DELAY)
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        for element in self.splitter.push(bytes) {
+            match serde_json::from_slice::<serde_json::Value>(&element) {
+                Ok(array) => self.handle_array(array),
+                Err(e) => {
+                    self.done = true;
+                    self.events.push_back(Err(FirebaseError::from(e)));
+                }
+            }
+        }
+    }
+
+    /// Handles an array of the WebChannel protocol. It consists of the array id and the payload.
+    fn handle_array(&mut self, array: serde_json::Value) {
+        let (array_id, payload) = match array.as_array().map(|a| a.as_slice()) {
+            Some([array_id, serde_json::Value::Array(payload)]) => (array_id.as_u64().unwrap_or_default(), payload),
+            _ => return,
+        };
+        self.last_array_id = self.last_array_id.max(array_id);
+        self.received = true;
+
+        // The message itself may be wrapped into another array
+        let message = match payload.first() {
+            Some(serde_json::Value::Array(inner)) => inner.first(),
+            message => message,
+        };
+        match message {
+            Some(serde_json::Value::String(control)) => match control.as_str() {
+                "c" => {
+                    self.session = payload.get(1).and_then(|sid| sid.as_str()).map(|sid| ChannelSession {
+                        sid: sid.to_owned(),
+                        http_session_id: None,
+                    })
+                }
+                // The server ended the session. A new session is opened with the last resume token.
+                "close" | "stop" => self.session = None,
+                // "noop" keeps the connection alive
+                _ => {}
+            },
+            Some(message) => {
+                if let Some(error) = message.get("error") {
+                    self.done = true;
+                    self.events.push_back(Err(FirebaseError::APIError(
+                        error.get("code").and_then(|c| c.as_u64()).unwrap_or_default() as usize,
+                        error
+                            .get("message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or_default()
+                            .to_owned(),
+                        "listen".to_owned(),
+                    )));
+                    return;
+                }
+                match serde_json::from_value::<dto::ListenResponse>(message.clone()) {
+                    Ok(response) => self.handle_response(response),
+                    Err(e) => {
+                        self.done = true;
+                        self.events.push_back(Err(FirebaseError::from(e)));
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn handle_response(&mut self, response: dto::ListenResponse) {
+        if let Some(change) = response.target_change {
+            let target_ids = change.target_ids.unwrap_or_default();
+            if !target_ids.is_empty() && !target_ids.contains(&LISTEN_TARGET_ID) {
+                return;
+            }
+            if let Some(resume_token) = change.resume_token {
+                self.target.resume_token = Some(resume_token);
+            }
+            match change.target_change_type.as_ref().map(|t| t.as_str()) {
+                Some("REMOVE") => {
+                    let cause = change.cause.unwrap_or_default();
+                    self.done = true;
+                    self.events.push_back(Err(FirebaseError::APIError(
+                        cause.code.unwrap_or_default() as usize,
+                        cause.message.unwrap_or_default(),
+                        "listen".to_owned(),
+                    )));
+                }
+                // The server sends all documents of the target again
+                Some("RESET") => {
+                    let known_documents = std::mem::take(&mut self.known_documents);
+                    self.reset_documents.extend(known_documents);
+                }
+                // A global "no change" marks a consistent snapshot
+                Some("NO_CHANGE") | None if target_ids.is_empty() && change.read_time.is_some() => {
+                    self.synced(&change.read_time);
+                }
+                _ => {}
+            }
+        } else if let Some(change) = response.document_change {
+            let document = match change.document {
+                Some(document) => document,
+                None => return,
+            };
+            if change.target_ids.unwrap_or_default().contains(&LISTEN_TARGET_ID) {
+                let was_known =
+                    self.reset_documents.remove(&document.name) || self.known_documents.contains(&document.name);
+                let event = match DocumentSnapshot::from_document(&document) {
+                    Err(e) => Err(e),
+                    Ok(snapshot) if was_known => Ok(ListenEvent::Modified(snapshot)),
+                    Ok(snapshot) => Ok(ListenEvent::Added(snapshot)),
+                };
+                self.known_documents.insert(document.name);
+                self.events.push_back(event);
+            } else if change
+                .removed_target_ids
+                .unwrap_or_default()
+                .contains(&LISTEN_TARGET_ID)
+            {
+                self.remove(document.name);
+            }
+        } else if let Some(delete) = response.document_delete {
+            if let Some(name) = delete.document {
+                self.remove(name);
+            }
+        } else if let Some(remove) = response.document_remove {
+            if let Some(name) = remove.document {
+                self.remove(name);
+            }
+        }
+    }
+
+    /// Reports a consistent snapshot. Documents that have not been sent again after a reset are removed.
+    fn synced(&mut self, read_time: &Option<String>) {
+        let mut removed: Vec<String> = self.reset_documents.drain().collect();
+        removed.sort();
+        for name in removed {
+            self.events.push_back(Ok(ListenEvent::Removed(name)));
+        }
+        let event = parse_time(read_time, "Failed to parse rfc3339 date from 'read_time' field")
+            .map(|read_time| ListenEvent::Synced(read_time.unwrap_or_else(Utc::now)));
+        self.events.push_back(event);
+    }
+
+    fn remove(&mut self, name: String) {
+        let was_known = self.reset_documents.remove(&name) | self.known_documents.remove(&name);
+        if was_known {
+            self.events.push_back(Ok(ListenEvent::Removed(name)));
+        }
+    }
+}
+
+/// A pseudo random number for request ids and cache busting url parameters
+fn random_id() -> u64 {
+    let now = Utc::now();
+    (now.timestamp() as u64)
+        .wrapping_mul(1_000_000_000)
+        .wrapping_add(u64::from(now.timestamp_subsec_nanos()))
+}
+
+/// Returns the http session id of a handshake response
+fn http_session_id(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get("X-HTTP-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_owned())
+}
+
+/// This type is returned by [`listen`]. Use it as an iterator of [`ListenEvent`]s.
+///
+/// The iterator blocks until the next change arrives. An interrupted connection is
+/// re-established and resumes where it stopped.
+pub struct Listener<'a, T, BEARER> {
+    auth: &'a BEARER,
+    state: ListenState<T>,
+    response: Option<reqwest::blocking::Response>,
+}
+
+impl<'a, T, BEARER> Listener<'a, T, BEARER>
+where
+    for<'b> T: Deserialize<'b>,
+    BEARER: FirebaseAuthBearer,
+{
+    /// Opens the back channel. A new session is opened first, if there is none.
+    fn connect(&mut self) -> Result<reqwest::blocking::Response> {
+        if self.state.session.is_none() {
+            let (params, body) = self.state.handshake_request()?;
+            let resp = match open_session(self.auth, &self.state.database, &params, &body) {
+                Ok(resp) => resp,
+                Err(FirebaseError::APIError(code, msg, context)) => {
+                    // The listen request has been rejected
+                    self.state.done = true;
+                    return Err(FirebaseError::APIError(code, msg, context));
+                }
+                Err(e) => return Err(e),
+            };
+            let http_session_id = http_session_id(resp.headers());
+            let body = resp.bytes()?;
+            self.state.open_session(&body, http_session_id)?;
+        }
+        let resp = self
+            .auth
+            .client()
+            .get(LISTEN_CHANNEL_URL)
+            .query(&self.state.back_channel_request())
+            .send()?;
+        extract_google_api_error(resp, || "listen".to_owned())
+    }
+}
+
+impl<'a, T, BEARER> Iterator for Listener<'a, T, BEARER>
+where
+    for<'b> T: Deserialize<'b>,
+    BEARER: FirebaseAuthBearer,
+{
+    type Item = Result<ListenEvent<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = [0u8; 8192];
+        loop {
+            if let Some(event) = self.state.events.pop_front() {
+                return Some(event);
+            }
+            if self.state.done {
+                return None;
+            }
+
+            let response = match self.response.take() {
+                Some(response) => response,
+                None => {
+                    std::thread::sleep(self.state.reconnect_delay());
+                    match self.connect() {
+                        Ok(response) => {
+                            self.state.connection_opened();
+                            response
+                        }
+                        Err(e) => match self.state.connection_failed(e) {
+                            Some(e) => return Some(Err(e)),
+                            None => continue,
+                        },
+                    }
+                }
+            };
+            let response = self.response.get_or_insert(response);
+
+            match response.read(&mut buffer) {
+                Ok(len) if len > 0 => self.state.push_bytes(&buffer[..len]),
+                // The connection has been closed or timed out. Reconnect with the last resume token.
+                _ => {
+                    self.response = None;
+                    self.state.connection_closed();
+                }
+            }
+        }
+    }
+}
+
+/// Sends the handshake, which opens a new session. The access token is sent as http header.
+fn open_session(
+    auth: &impl FirebaseAuthBearer,
+    database: &str,
+    params: &[(&str, String)],
+    body: &[(&str, String)],
+) -> Result<reqwest::blocking::Response> {
+    exp_backoff(
+        || {
+            let resp = auth
+                .client()
+                .post(LISTEN_CHANNEL_URL)
+                .bearer_auth(auth.access_token())
+                .header("google-cloud-resource-prefix", database)
+                .query(params)
+                .form(body)
+                .send()
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error(resp, || "listen".to_owned()) {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+}
+
+/// [Async] Sends the handshake, which opens a new session. The access token is sent as http header.
+async fn open_session_async(
+    auth: &impl FirebaseAuthBearer,
+    database: &str,
+    params: &[(&str, String)],
+    body: &[(&str, String)],
+) -> Result<reqwest::Response> {
+    exp_backoff_async(
+        || async {
+            let resp = auth
+                .client_async()
+                .post(LISTEN_CHANNEL_URL)
+                .bearer_auth(auth.access_token())
+                .header("google-cloud-resource-prefix", database)
+                .query(params)
+                .form(body)
+                .send()
+                .await
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_async(resp, || "listen".to_owned()).await {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+    .await
+}
+
+/// [Async] Opens the back channel. A new session is opened first, if there is none.
+async fn connect_async<T>(auth: &impl FirebaseAuthBearer, state: &mut ListenState<T>) -> Result<reqwest::Response>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    if state.session.is_none() {
+        let (params, body) = state.handshake_request()?;
+        let resp = match open_session_async(auth, &state.database, &params, &body).await {
+            Ok(resp) => resp,
+            Err(FirebaseError::APIError(code, msg, context)) => {
+                // The listen request has been rejected
+                state.done = true;
+                return Err(FirebaseError::APIError(code, msg, context));
+            }
+            Err(e) => return Err(e),
+        };
+        let http_session_id = http_session_id(resp.headers());
+        let body = resp.bytes().await?;
+        state.open_session(&body, http_session_id)?;
+    }
+    let resp = auth
+        .client_async()
+        .get(LISTEN_CHANNEL_URL)
+        .query(&state.back_channel_request())
+        .send()
+        .await?;
+    extract_google_api_error_async(resp, || "listen".to_owned()).await
+}
+
+///
+/// Listen to changes of a document or of all documents matching a query.
+///
+/// Firestore does not offer the Listen stream via REST. Like the Firebase web SDKs, this uses the WebChannel
+/// protocol: A handshake opens a session with the listen request and the changes are streamed via long-lived
+/// http requests. The returned [`Listener`] blocks until the next change arrives.
+/// All initially matching documents are reported as [`ListenEvent::Added`].
+///
+/// Resume tokens are tracked and a closed or timed out connection is re-established transparently, for example
+/// when the request timeout of the http client (30 seconds by default) has elapsed. Failed connection attempts
+/// are retried with an increasing delay. After 10 failed attempts in a row, the last error is returned and ends
+/// the listener. A rejected listen request or a removed target ends the listener, too.
+///
+/// Example:
+/// ```no_run
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents::{self, ListenEvent, ListenTarget};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let listener = documents::listen::<DemoDTO, _>(&session, ListenTarget::document("tests/service_test"));
+/// for event in listener {
+///     match event? {
+///         ListenEvent::Added(snapshot) | ListenEvent::Modified(snapshot) => {
+///             println!("{}: {:?}", snapshot.id(), snapshot.data)
+///         }
+///         ListenEvent::Removed(name) => println!("{} removed", name),
+///         ListenEvent::Synced(read_time) => println!("in sync at {}", read_time),
+///     }
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'target' The documents to listen to
+pub fn listen<T, BEARER>(auth: &BEARER, target: ListenTarget) -> Listener<T, BEARER>
+where
+    for<'b> T: Deserialize<'b>,
+    BEARER: FirebaseAuthBearer,
+{
+    Listener {
+        auth,
+        state: ListenState::new(target.to_target(auth.project_id()), auth.project_id()),
+        response: None,
+    }
+}
+
+///
+/// [Async] Listen to changes of a document or of all documents matching a query.
+///
+/// Returns a [`Stream`] of [`ListenEvent`]s. See [`listen`] for details.
+/// The stream must be polled within a tokio runtime.
+///
+/// Example:
+/// ```no_run
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents::{self, ListenEvent, ListenTarget};
+/// use futures::StreamExt;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+/// # let mut rt = tokio::runtime::Runtime::new()?;
+///
+/// rt.block_on(async {
+///     let events = documents::listen_async::<DemoDTO, _>(&session, ListenTarget::document("tests/service_test"));
+///     futures::pin_mut!(events);
+///     while let Some(event) = events.next().await {
+///         if let ListenEvent::Modified(snapshot) = event? {
+///             println!("{:?}", snapshot.data);
+///         }
+///     }
+///     Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// })?;
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'target' The documents to listen to
+pub fn listen_async<'a, T, BEARER>(
+    auth: &'a BEARER,
+    target: ListenTarget,
+) -> impl Stream<Item = Result<ListenEvent<T>>> + 'a
+where
+    for<'b> T: Deserialize<'b>,
+    T: 'a,
+    BEARER: FirebaseAuthBearer,
+{
+    let state = ListenState::new(target.to_target(auth.project_id()), auth.project_id());
+    let response: Option<reqwest::Response> = None;
+
+    stream::unfold((state, response), move |(mut state, mut response)| async move {
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Some((event, (state, response)));
+            }
+            if state.done {
+                return None;
+            }
+
+            let mut current = match response.take() {
+                Some(current) => current,
+                None => {
+                    let delay = state.reconnect_delay();
+                    if delay > Duration::from_secs(0) {
+                        tokio::time::delay_for(delay).await;
+                    }
+                    match connect_async(auth, &mut state).await {
+                        Ok(current) => {
+                            state.connection_opened();
+                            current
+                        }
+                        Err(e) => match state.connection_failed(e) {
+                            Some(e) => return Some((Err(e), (state, None))),
+                            None => continue,
+                        },
+                    }
+                }
+            };
+
+            match current.chunk().await {
+                Ok(Some(bytes)) => {
+                    state.push_bytes(&bytes);
+                    response = Some(current);
+                }
+                // A closed or timed out connection is re-established with the last resume token
+                Ok(None) | Err(_) => state.connection_closed(),
+            }
+        }
+    })
+}
+
+#[test]
+fn json_array_splitter_test() {
+    let mut splitter = JsonArraySplitter::default();
+    assert!(splitter.push(b"[{\"a\": \"}\\\"\"").is_empty());
+    let elements = splitter.push(b", \"b\": [1, {}]}\n,{\"c\": 2}");
+    assert_eq!(elements.len(), 2);
+    assert_eq!(elements[0], b"{\"a\": \"}\\\"\", \"b\": [1, {}]}".to_vec());
+    assert_eq!(elements[1], b"{\"c\": 2}".to_vec());
+    assert!(splitter.push(b"]").is_empty());
+}
+
+#[test]
+fn listen_state_test() {
+    let target = ListenTarget::document("tests/a").to_target("p");
+    let mut state = ListenState::<serde_json::Value>::new(target, "p");
+    let (params, body) = state.handshake_request().unwrap();
+    assert!(params.contains(&("database", "projects/p/databases/(default)".to_owned())));
+    assert!(!params.iter().any(|(name, _)| *name == "$httpHeaders"));
+    let request: serde_json::Value = serde_json::from_str(&body[2].1).unwrap();
+    assert_eq!(request["database"], "projects/p/databases/(default)");
+    assert_eq!(
+        request["addTarget"]["documents"]["documents"][0],
+        "projects/p/databases/(default)/documents/tests/a"
+    );
+
+    // Recorded frames of a session. Their length prefixes count UTF-16 code units.
+    state
+        .open_session(b"51\n[[0,[\"c\",\"SID1\",\"\",8,14,30000]]]\n", Some("gs1".to_owned()))
+        .unwrap();
+    let params = state.back_channel_request();
+    assert!(params.contains(&("SID", "SID1".to_owned())));
+    assert!(params.contains(&("gsessionid", "gs1".to_owned())));
+    assert!(params.contains(&("AID", "0".to_owned())));
+
+    let document = |id: &str, n: i64| {
+        serde_json::json!({ "documentChange": { "document": {
+            "name": format!("projects/p/databases/(default)/documents/tests/{}", id),
+            "fields": { "n": { "integerValue": n.to_string() } },
+            "createTime": "2020-01-22T10:00:00Z",
+            "updateTime": "2020-01-22T10:00:00Z"
+        }, "targetIds": [1] } })
+    };
+    let frame = |arrays: serde_json::Value| {
+        let json = arrays.to_string();
+        format!("{}\n{}\n", json.len(), json)
+    };
+    let frames = frame(serde_json::json!([
+        [1, [{ "targetChange": { "targetChangeType": "ADD", "targetIds": [1] } }]],
+        [2, [document("a", 1)]],
+        [3, [{ "targetChange": { "targetChangeType": "CURRENT", "targetIds": [1], "resumeToken": "dG9rZW4x" } }]],
+        [4, [{ "targetChange": { "resumeToken": "dG9rZW4y", "readTime": "2020-01-22T10:00:01Z" } }]]
+    ]));
+    // Chunks do not align with frames
+    let (first, second) = frames.as_bytes().split_at(100);
+    state.push_bytes(first);
+    state.push_bytes(second);
+
+    match state.events.pop_front() {
+        Some(Ok(ListenEvent::Added(snapshot))) => assert_eq!(snapshot.data.unwrap()["n"], 1),
+        event => panic!("Unexpected event {:?}", event),
+    }
+    match state.events.pop_front() {
+        Some(Ok(ListenEvent::Synced(read_time))) => assert_eq!(read_time.to_rfc3339(), "2020-01-22T10:00:01+00:00"),
+        event => panic!("Unexpected event {:?}", event),
+    }
+    assert!(state.events.is_empty());
+    assert_eq!(state.target.resume_token.as_ref().unwrap(), "dG9rZW4y");
+    assert_eq!(state.last_array_id, 4);
+
+    // After a reset, documents that are not sent again have been removed
+    state.push_bytes(
+        frame(serde_json::json!([
+            [5, ["noop"]],
+            [6, [{ "targetChange": { "targetChangeType": "RESET", "targetIds": [1] } }]],
+            [7, [document("b", 2)]],
+            [8, [{ "targetChange": { "readTime": "2020-01-22T10:00:02Z" } }]]
+        ]))
+        .as_bytes(),
+    );
+    let events: Vec<_> = state.events.drain(..).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 3);
+    assert!(match &events[0] {
+        ListenEvent::Added(snapshot) => snapshot.id() == "b",
+        _ => false,
+    });
+    assert!(match &events[1] {
+        ListenEvent::Removed(name) => name.ends_with("tests/a"),
+        _ => false,
+    });
+
+    // A closed session is re-opened, an error ends the stream
+    state.push_bytes(frame(serde_json::json!([[9, ["close"]]])).as_bytes());
+    assert!(state.session.is_none());
+    state.push_bytes(
+        frame(
+            serde_json::json!([[10, [{ "error": { "code": 7, "message": "Missing or insufficient permissions." } }]]]),
+        )
+        .as_bytes(),
+    );
+    assert!(state.done);
+    assert!(state.events.pop_front().unwrap().is_err());
+
+    state.failures = 3;
+    assert_eq!(state.reconnect_delay(), Duration::from_secs(2));
+    state.failures = 20;
+    assert_eq!(state.reconnect_delay(), MAX_RECONNECT_DELAY);
+
+    // Failed connection attempts end the stream only after too many of them
+    state.done = false;
+    for _ in 1..MAX_FAILED_CONNECTIONS {
+        assert!(state.connection_failed(FirebaseError::Generic("offline")).is_none());
+    }
+    assert!(state.connection_failed(FirebaseError::Generic("offline")).is_some());
+    assert!(state.done);
+}
@@ -5,23 +5,53 @@
 
 use super::backoff::*;
 use super::dto;
-use super::errors::{extract_google_api_error, extract_google_api_error_async, FirebaseError, Result};
-use super::firebase_rest_to_rust::{document_to_pod, pod_to_document};
+use super::errors::{
+    extract_google_api_error, extract_google_api_error_async, extract_google_api_error_with,
+    extract_google_api_error_with_async, FirebaseError, Result,
+};
+use super::firebase_rest_to_rust::{
+    document_to_pod, pod_to_document, pod_to_document_with, serde_value_to_firebase_value,
+};
+use super::value_serde::SerializeOptions;
 use super::FirebaseAuthBearer;
 
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+mod aggregate;
+mod batch;
+mod collections;
 mod delete;
+mod delete_recursive;
+mod field_path;
 mod list;
+mod listen;
+mod paginate;
+mod partition;
 mod query;
+mod query_builder;
 mod read;
+mod snapshot;
+mod transaction;
+mod transform;
 mod write;
 
+pub use aggregate::*;
+pub use batch::*;
+pub use collections::*;
 pub use delete::*;
+pub use delete_recursive::*;
+pub use field_path::*;
 pub use list::*;
+pub use listen::*;
+pub use paginate::*;
+pub use partition::*;
 pub use query::*;
+pub use query_builder::*;
 pub use read::*;
+pub use snapshot::*;
+pub use transaction::*;
+pub use transform::*;
 pub use write::*;
 
 /// An [`Iterator`] implementation that provides a join method
@@ -60,6 +90,14 @@ fn firebase_url_query(v1: &str) -> String {
     )
 }
 
+#[inline]
+fn firebase_url_action(v1: &str, v2: &str) -> String {
+    format!(
+        "https://firestore.googleapis.com/v1/projects/{}/databases/(default)/documents:{}",
+        v1, v2
+    )
+}
+
 #[inline]
 fn firebase_url_base(v1: &str) -> String {
     format!("https://firestore.googleapis.com/v1/{}", v1)
@@ -81,6 +119,18 @@ fn firebase_url(v1: &str, v2: &str) -> String {
     )
 }
 
+#[inline]
+fn document_name(v1: &str, v2: &str) -> String {
+    format!("projects/{}/databases/(default)/documents/{}", v1, v2)
+}
+
+/// Converts a filter, cursor or field transform value.
+/// Fails for values that can not be represented, like an integer that does not fit into 64 bit.
+#[inline]
+fn value_to_firebase(value: &serde_json::Value) -> Result<dto::Value> {
+    serde_value_to_firebase_value(value)
+}
+
 /// Converts an absolute path like "projects/{PROJECT_ID}/databases/(default)/documents/my_collection/document_id"
 /// into a relative document path like "my_collection/document_id"
 ///
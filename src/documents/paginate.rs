@@ -0,0 +1,398 @@
+use super::*;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use std::vec::IntoIter;
+
+/// The state of a paginated query, shared by [`Paginate`] and [`QueryBuilder::paginate_async`]
+struct Pagination {
+    url: String,
+    context: String,
+    request: dto::RunQueryRequest,
+    page_size: u32,
+    /// The number of documents still to fetch, if the query has a limit
+    remaining: Option<u32>,
+    documents: IntoIter<dto::Document>,
+    done: bool,
+}
+
+impl Pagination {
+    fn new(query: &QueryBuilder, project_id: &str, page_size: u32) -> Self {
+        let mut request = query.query_request();
+        let structured_query = request.structured_query.get_or_insert_with(Default::default);
+        let remaining = structured_query.limit.map(|limit| limit.max(0) as u32);
+        complete_order_by(structured_query);
+
+        Pagination {
+            url: query.url(project_id),
+            context: query.collection_id().to_owned(),
+            request,
+            page_size: page_size.max(1),
+            done: remaining == Some(0),
+            remaining,
+            documents: Vec::new().into_iter(),
+        }
+    }
+
+    /// The request for the next page
+    fn page_request(&mut self) -> &dto::RunQueryRequest {
+        let limit = match self.remaining {
+            Some(remaining) => remaining.min(self.page_size),
+            None => self.page_size,
+        };
+        if let Some(structured_query) = self.request.structured_query.as_mut() {
+            structured_query.limit = Some(clamp_to_i32(limit));
+        }
+        &self.request
+    }
+
+    /// Stores the documents of a fetched page and continues after the last document of it
+    fn push_page(&mut self, responses: Vec<dto::RunQueryResponse>) -> Result<()> {
+        let documents: Vec<dto::Document> = responses.into_iter().filter_map(|r| r.document).collect();
+        let requested = self
+            .request
+            .structured_query
+            .as_ref()
+            .and_then(|q| q.limit)
+            .unwrap_or_default() as usize;
+
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining = remaining.saturating_sub(documents.len() as u32);
+        }
+        self.done = documents.len() < requested || self.remaining == Some(0);
+
+        if let (Some(last), Some(structured_query)) = (documents.last(), self.request.structured_query.as_mut()) {
+            structured_query.start_at = Some(start_after(last, structured_query.order_by.as_ref())?);
+            // The offset only applies to the first page
+            structured_query.offset = None;
+        }
+        self.documents = documents.into_iter();
+        Ok(())
+    }
+}
+
+/// Firestore implicitly orders by fields with inequality filters and by the document name.
+/// Cursors require those orders to be explicit.
+fn complete_order_by(structured_query: &mut dto::StructuredQuery) {
+    let mut order_by = structured_query.order_by.take().unwrap_or_default();
+    let is_ordered = |order_by: &[dto::Order], field_path: &str| {
+        order_by
+            .iter()
+            .any(|o| o.field.as_ref().map(|f| f.field_path.as_str()) == Some(field_path))
+    };
+
+    let mut inequality_fields = Vec::new();
+    if let Some(filter) = structured_query.where_.as_ref() {
+        collect_inequality_fields(filter, &mut inequality_fields);
+    }
+    inequality_fields.sort();
+    for field_path in inequality_fields {
+        if !is_ordered(&order_by, &field_path) {
+            order_by.push(dto::Order {
+                field: Some(dto::FieldReference { field_path }),
+                direction: None,
+            });
+        }
+    }
+
+    if !is_ordered(&order_by, "__name__") {
+        let direction = order_by.last().and_then(|o| o.direction.clone());
+        order_by.push(dto::Order {
+            field: Some(dto::FieldReference {
+                field_path: "__name__".to_owned(),
+            }),
+            direction,
+        });
+    }
+
+    // Cursors are built from the ordered fields, so they must be part of a projection
+    if let Some(fields) = structured_query.select.as_mut().and_then(|s| s.fields.as_mut()) {
+        for order in &order_by {
+            if let Some(field) = order.field.as_ref() {
+                if field.field_path != "__name__" && !fields.iter().any(|f| f.field_path == field.field_path) {
+                    fields.push(field.clone());
+                }
+            }
+        }
+    }
+    structured_query.order_by = Some(order_by);
+}
+
+fn collect_inequality_fields(filter: &dto::Filter, fields: &mut Vec<String>) {
+    if let Some(field_filter) = filter.field_filter.as_ref() {
+        let is_inequality = match field_filter.op {
+            dto::FieldOperator::LESS_THAN
+            | dto::FieldOperator::LESS_THAN_OR_EQUAL
+            | dto::FieldOperator::GREATER_THAN
+            | dto::FieldOperator::GREATER_THAN_OR_EQUAL
+            | dto::FieldOperator::NOT_EQUAL
+            | dto::FieldOperator::NOT_IN => true,
+            _ => false,
+        };
+        if is_inequality && !fields.contains(&field_filter.field.field_path) {
+            fields.push(field_filter.field.field_path.clone());
+        }
+    }
+    if let Some(composite_filter) = filter.composite_filter.as_ref() {
+        for filter in &composite_filter.filters {
+            collect_inequality_fields(filter, fields);
+        }
+    }
+}
+
+/// A cursor that starts after the given document, built from the values of the ordered fields
+fn start_after(document: &dto::Document, order_by: Option<&Vec<dto::Order>>) -> Result<dto::Cursor> {
+    let values = order_by
+        .into_iter()
+        .flatten()
+        .filter_map(|order| order.field.as_ref())
+        .map(|field| {
+            if field.field_path == "__name__" {
+                return Ok(dto::Value {
+                    reference_value: Some(document.name.clone()),
+                    ..Default::default()
+                });
+            }
+            field_value(document.fields.as_ref(), &field.field_path)
+                .cloned()
+                .ok_or_else(|| FirebaseError::Generic("A document of a paginated query lacks an ordered field"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(dto::Cursor {
+        values: Some(values),
+        before: Some(false),
+    })
+}
+
+/// Returns the value of the given field path, for example "a_map.b"
+fn field_value<'a>(fields: Option<&'a HashMap<String, dto::Value>>, field_path: &str) -> Option<&'a dto::Value> {
+    let mut fields = fields;
+    let mut value = None;
+    for name in split_field_path(field_path) {
+        value = fields?.get(&name);
+        fields = value.and_then(|v| v.map_value.as_ref()).map(|m| &m.fields);
+    }
+    value
+}
+
+impl QueryBuilder {
+    ///
+    /// Executes this query page by page. Only a single page of documents is kept in memory.
+    ///
+    /// Each page continues after the last document of the previous page. The cursor is built from the values
+    /// of the ordered fields and the document name. A limit of the query applies to all pages together.
+    ///
+    /// Example:
+    /// ```rust
+    /// use firestore_db_and_auth::documents::{field, QueryBuilder};
+    /// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+    ///
+    /// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+    ///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+    /// # let session = ServiceSession::new(credentials)?;
+    ///
+    /// let query = QueryBuilder::collection("tests").filter(field("an_int").gte(10)?);
+    /// for document in query.paginate(&session, 500) {
+    ///     println!("{}", document?.name);
+    /// }
+    /// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+    /// ```
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    /// * 'page_size' The number of documents per page
+    pub fn paginate<'a, BEARER>(&self, auth: &'a BEARER, page_size: u32) -> Paginate<'a, BEARER>
+    where
+        BEARER: FirebaseAuthBearer,
+    {
+        Paginate {
+            auth,
+            state: Pagination::new(self, auth.project_id(), page_size),
+        }
+    }
+
+    ///
+    /// [Async] Executes this query page by page. Returns a [`Stream`] of documents.
+    /// Only a single page of documents is kept in memory. See [`QueryBuilder::paginate`].
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    /// * 'page_size' The number of documents per page
+    pub fn paginate_async<'a, BEARER>(
+        &self,
+        auth: &'a BEARER,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<dto::Document>> + 'a
+    where
+        BEARER: FirebaseAuthBearer,
+    {
+        let state = Pagination::new(self, auth.project_id(), page_size);
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(document) = state.documents.next() {
+                    return Some((Ok(document), state));
+                }
+                if state.done {
+                    return None;
+                }
+                let url = state.url.clone();
+                let context = state.context.clone();
+                let page = run_query_async(auth, &url, state.page_request(), &context)
+                    .await
+                    .and_then(|responses| state.push_page(responses));
+                if let Err(e) = page {
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+            }
+        })
+    }
+
+    ///
+    /// Executes this query page by page and deserializes the documents. See [`QueryBuilder::paginate`].
+    ///
+    /// Example:
+    /// ```rust
+    /// # use serde::{Serialize, Deserialize};
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct DemoDTO { a_string: String, an_int: u32, }
+    ///
+    /// use firestore_db_and_auth::documents::{field, DocumentSnapshot, QueryBuilder};
+    /// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+    ///
+    /// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+    ///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+    /// # let session = ServiceSession::new(credentials)?;
+    ///
+    /// let query = QueryBuilder::collection("tests").filter(field("an_int").gte(10)?);
+    /// for snapshot in query.paginate_typed::<DemoDTO, _>(&session, 500) {
+    ///     let snapshot = snapshot?;
+    ///     println!("{}: {:?}", snapshot.id(), snapshot.data);
+    /// }
+    /// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+    /// ```
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    /// * 'page_size' The number of documents per page
+    pub fn paginate_typed<'a, T, BEARER>(
+        &self,
+        auth: &'a BEARER,
+        page_size: u32,
+    ) -> impl Iterator<Item = Result<DocumentSnapshot<T>>> + 'a
+    where
+        for<'b> T: Deserialize<'b>,
+        T: 'a,
+        BEARER: FirebaseAuthBearer,
+    {
+        self.paginate(auth, page_size)
+            .map(|doc| doc.and_then(|doc| DocumentSnapshot::from_document(&doc)))
+    }
+
+    ///
+    /// [Async] Executes this query page by page and deserializes the documents.
+    /// Returns a [`Stream`] of [`DocumentSnapshot`]s. See [`QueryBuilder::paginate_async`].
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    /// * 'page_size' The number of documents per page
+    pub fn paginate_typed_async<'a, T, BEARER>(
+        &self,
+        auth: &'a BEARER,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<DocumentSnapshot<T>>> + 'a
+    where
+        for<'b> T: Deserialize<'b>,
+        T: 'a,
+        BEARER: FirebaseAuthBearer,
+    {
+        self.paginate_async(auth, page_size)
+            .map(|doc| doc.and_then(|doc| DocumentSnapshot::from_document(&doc)))
+    }
+}
+
+/// This type is returned as a result by [`QueryBuilder::paginate`].
+/// Use it as an iterator. New pages are fetched lazily.
+pub struct Paginate<'a, BEARER> {
+    auth: &'a BEARER,
+    state: Pagination,
+}
+
+impl<'a, BEARER> Iterator for Paginate<'a, BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    type Item = Result<dto::Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(document) = self.state.documents.next() {
+                return Some(Ok(document));
+            }
+            if self.state.done {
+                return None;
+            }
+            let url = self.state.url.clone();
+            let context = self.state.context.clone();
+            let page = run_query(self.auth, &url, self.state.page_request(), &context)
+                .and_then(|responses| self.state.push_page(responses));
+            if let Err(e) = page {
+                self.state.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[test]
+fn pagination_test() {
+    let query = QueryBuilder::collection("cars")
+        .filter(field("price").lt(100).unwrap())
+        .order_by("type", false)
+        .select(&["name"])
+        .offset(5)
+        .limit(3);
+    let mut state = Pagination::new(&query, "p", 2);
+    let json = serde_json::to_value(state.page_request()).unwrap();
+    assert_eq!(json["structuredQuery"]["limit"], 2);
+    assert_eq!(json["structuredQuery"]["offset"], 5);
+    assert_eq!(
+        json["structuredQuery"]["orderBy"],
+        serde_json::json!([
+            { "field": { "fieldPath": "type" }, "direction": "DESCENDING" },
+            { "field": { "fieldPath": "price" } },
+            { "field": { "fieldPath": "__name__" } }
+        ])
+    );
+    assert_eq!(json["structuredQuery"]["select"]["fields"].as_array().unwrap().len(), 3);
+
+    let document = |name: &str| {
+        let mut document = pod_to_document(&serde_json::json!({ "type": "suv", "price": 50 })).unwrap();
+        document.name = format!("projects/p/databases/(default)/documents/cars/{}", name);
+        dto::RunQueryResponse {
+            document: Some(document),
+            ..Default::default()
+        }
+    };
+    state.push_page(vec![document("c1"), document("c2")]).unwrap();
+    assert!(!state.done);
+    assert_eq!(state.documents.len(), 2);
+
+    let json = serde_json::to_value(state.page_request()).unwrap();
+    assert_eq!(json["structuredQuery"]["limit"], 1);
+    assert!(json["structuredQuery"].get("offset").is_none());
+    assert_eq!(
+        json["structuredQuery"]["startAt"],
+        serde_json::json!({
+            "values": [
+                { "stringValue": "suv" },
+                { "integerValue": "50" },
+                { "referenceValue": "projects/p/databases/(default)/documents/cars/c2" }
+            ],
+            "before": false
+        })
+    );
+
+    state.push_page(vec![document("c3")]).unwrap();
+    assert!(state.done);
+}
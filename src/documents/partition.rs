@@ -0,0 +1,374 @@
+use super::*;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+
+/// The number of documents per page when [`parallel_scan_async`] queries a partition
+const SCAN_PAGE_SIZE: u32 = 500;
+
+/// A range of the results of a query, returned by [`partition_query`].
+///
+/// The partitions of a query are disjoint and together cover all results of the query.
+/// Apply a partition to the query with [`QueryPartition::apply`] and run the resulting queries in parallel.
+#[derive(Clone, Debug, Default)]
+pub struct QueryPartition {
+    /// The partition starts at this cursor, including it. None denotes the start of the results.
+    pub start_at: Option<dto::Cursor>,
+    /// The partition ends before this cursor. None denotes the end of the results.
+    pub end_before: Option<dto::Cursor>,
+}
+
+impl QueryPartition {
+    /// Restricts the given query to this partition. Start and end cursors of the query are replaced.
+    pub fn apply(&self, query: QueryBuilder) -> QueryBuilder {
+        let cursor = |cursor: &dto::Cursor| dto::Cursor {
+            values: cursor.values.clone(),
+            before: Some(true),
+        };
+        query.cursors(self.start_at.as_ref().map(cursor), self.end_before.as_ref().map(cursor))
+    }
+}
+
+/// The request of a partitionQuery. Partitioning requires an ascending order by document name.
+fn partition_request(query: &QueryBuilder, partitions: u32, page_token: Option<String>) -> dto::PartitionQueryRequest {
+    let query_request = query.query_request();
+    let mut structured_query = query_request.structured_query.unwrap_or_default();
+    if structured_query.order_by.is_none() {
+        structured_query.order_by = Some(vec![dto::Order {
+            field: Some(dto::FieldReference {
+                field_path: "__name__".to_owned(),
+            }),
+            direction: None,
+        }]);
+    }
+    dto::PartitionQueryRequest {
+        structured_query: Some(structured_query),
+        partition_count: partitions.to_string(),
+        page_token,
+        read_time: query_request.read_time,
+        ..Default::default()
+    }
+}
+
+/// Sorts the split points of all pages and turns them into consecutive partitions
+fn to_partitions(mut cursors: Vec<dto::Cursor>) -> Vec<QueryPartition> {
+    // Cursors of different pages are not ordered with respect to each other
+    let key = |cursor: &dto::Cursor| -> Vec<String> {
+        let name = cursor
+            .values
+            .as_ref()
+            .and_then(|values| values.first())
+            .and_then(|value| value.reference_value.as_ref());
+        name.map(|name| name.split('/').map(|segment| segment.to_owned()).collect())
+            .unwrap_or_default()
+    };
+    cursors.sort_by_key(key);
+
+    let mut partitions = Vec::with_capacity(cursors.len() + 1);
+    let mut start_at = None;
+    for cursor in cursors {
+        partitions.push(QueryPartition {
+            start_at: start_at.take(),
+            end_before: Some(cursor.clone()),
+        });
+        start_at = Some(cursor);
+    }
+    partitions.push(QueryPartition {
+        start_at,
+        end_before: None,
+    });
+    partitions
+}
+
+fn get_partitions_page(
+    auth: &impl FirebaseAuthBearer,
+    url: &str,
+    request: &dto::PartitionQueryRequest,
+    context: &str,
+) -> Result<dto::PartitionQueryResponse> {
+    let resp = exp_backoff(
+        || {
+            let resp = auth
+                .client()
+                .post(url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(request)
+                .send()
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error(resp, || context.to_owned()) {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )?;
+
+    let json: dto::PartitionQueryResponse = resp.json()?;
+    Ok(json)
+}
+
+async fn get_partitions_page_async(
+    auth: &impl FirebaseAuthBearer,
+    url: &str,
+    request: &dto::PartitionQueryRequest,
+    context: &str,
+) -> Result<dto::PartitionQueryResponse> {
+    let resp = exp_backoff_async(
+        || async {
+            let resp = auth
+                .client_async()
+                .post(url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(request)
+                .send()
+                .await
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_async(resp, || context.to_owned()).await {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+    .await?;
+
+    let json: dto::PartitionQueryResponse = resp.json().await?;
+    Ok(json)
+}
+
+///
+/// Splits the results of a query into ranges that can be queried in parallel.
+///
+/// Firestore only partitions collection group queries without filters, orders (except by document name),
+/// limits, offsets and cursors. Fewer partitions than requested may be returned, but always at least one.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{self, QueryBuilder};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let query = QueryBuilder::collection_group("tests");
+/// for partition in documents::partition_query(&session, &query, 4)? {
+///     let count = partition.apply(query.clone()).run(&session)?.count();
+///     println!("{} documents", count);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query, usually a [`QueryBuilder::collection_group`] query
+/// * 'partitions' The desired number of partitions
+pub fn partition_query(
+    auth: &impl FirebaseAuthBearer,
+    query: &QueryBuilder,
+    partitions: u32,
+) -> Result<Vec<QueryPartition>> {
+    let url = query.action_url(auth.project_id(), "partitionQuery");
+    let mut cursors = Vec::new();
+    let mut page_token = None;
+    // A single partition does not need any split points
+    if partitions <= 1 {
+        return Ok(to_partitions(cursors));
+    }
+    loop {
+        let request = partition_request(query, partitions, page_token);
+        let response = get_partitions_page(auth, &url, &request, query.collection_id())?;
+        cursors.extend(response.partitions.unwrap_or_default());
+        page_token = response.next_page_token;
+        if page_token.is_none() {
+            return Ok(to_partitions(cursors));
+        }
+    }
+}
+
+///
+/// [Async] Splits the results of a query into ranges that can be queried in parallel. See [`partition_query`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query, usually a [`QueryBuilder::collection_group`] query
+/// * 'partitions' The desired number of partitions
+pub async fn partition_query_async(
+    auth: &impl FirebaseAuthBearer,
+    query: &QueryBuilder,
+    partitions: u32,
+) -> Result<Vec<QueryPartition>> {
+    let url = query.action_url(auth.project_id(), "partitionQuery");
+    let mut cursors = Vec::new();
+    let mut page_token = None;
+    if partitions <= 1 {
+        return Ok(to_partitions(cursors));
+    }
+    loop {
+        let request = partition_request(query, partitions, page_token);
+        let response = get_partitions_page_async(auth, &url, &request, query.collection_id()).await?;
+        cursors.extend(response.partitions.unwrap_or_default());
+        page_token = response.next_page_token;
+        if page_token.is_none() {
+            return Ok(to_partitions(cursors));
+        }
+    }
+}
+
+///
+/// [Async] Scans all results of a query in parallel. Returns a [`Stream`] of deserialized documents.
+///
+/// The query is split via [`partition_query_async`] and up to `concurrency` partitions are queried at the same time.
+/// Each partition is fetched page by page, so only a single page per queried partition is kept in memory.
+/// Documents of different partitions are interleaved and not returned in the order of the query.
+///
+/// Example:
+/// ```no_run
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents::{self, QueryBuilder};
+/// use futures::StreamExt;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+/// # let mut rt = tokio::runtime::Runtime::new()?;
+///
+/// rt.block_on(async {
+///     let query = QueryBuilder::collection_group("tests");
+///     let documents = documents::parallel_scan_async::<DemoDTO, _>(&session, query, 16, 4);
+///     futures::pin_mut!(documents);
+///     while let Some(snapshot) = documents.next().await {
+///         println!("{:?}", snapshot?.data);
+///     }
+///     Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// })?;
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'query' The query, usually a [`QueryBuilder::collection_group`] query
+/// * 'partitions' The desired number of partitions
+/// * 'concurrency' The maximum number of partitions that are queried at the same time
+pub fn parallel_scan_async<'a, T, BEARER>(
+    auth: &'a BEARER,
+    query: QueryBuilder,
+    partitions: u32,
+    concurrency: usize,
+) -> impl Stream<Item = Result<DocumentSnapshot<T>>> + 'a
+where
+    for<'b> T: Deserialize<'b>,
+    T: 'a,
+    BEARER: FirebaseAuthBearer,
+{
+    let queries = async move {
+        let partitions = partition_query_async(auth, &query, partitions).await?;
+        Ok::<_, FirebaseError>(
+            partitions
+                .iter()
+                .map(|partition| partition.apply(query.clone()))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    stream::once(queries)
+        .map(move |queries| match queries {
+            Ok(queries) => scan_partitions(auth, queries, concurrency.max(1)).left_stream(),
+            Err(e) => stream::once(future::ready(Err(e))).right_stream(),
+        })
+        .flatten()
+        .map(|document| document.and_then(|document| DocumentSnapshot::from_document(&document)))
+}
+
+/// Pages through the given partition queries, up to `concurrency` of them at the same time
+fn scan_partitions<'a, BEARER>(
+    auth: &'a BEARER,
+    queries: Vec<QueryBuilder>,
+    concurrency: usize,
+) -> impl Stream<Item = Result<dto::Document>> + 'a
+where
+    BEARER: FirebaseAuthBearer,
+{
+    let state = (queries.into_iter(), stream::SelectAll::new());
+
+    stream::unfold(state, move |(mut queries, mut active)| async move {
+        loop {
+            while active.len() < concurrency {
+                match queries.next() {
+                    Some(query) => active.push(Box::pin(query.paginate_async(auth, SCAN_PAGE_SIZE))),
+                    None => break,
+                }
+            }
+            match active.next().await {
+                Some(document) => return Some((document, (queries, active))),
+                // All active partitions are exhausted
+                None if queries.len() == 0 => return None,
+                None => {}
+            }
+        }
+    })
+}
+
+#[test]
+fn partition_test() {
+    let query = QueryBuilder::collection_group("orders").parent("users/u1");
+    assert_eq!(
+        query.action_url("p", "partitionQuery"),
+        "https://firestore.googleapis.com/v1/projects/p/databases/(default)/documents/users/u1:partitionQuery"
+    );
+    let json = serde_json::to_value(partition_request(&query, 3, None)).unwrap();
+    assert_eq!(json["partitionCount"], "3");
+    assert_eq!(
+        json["structuredQuery"]["orderBy"],
+        serde_json::json!([{ "field": { "fieldPath": "__name__" } }])
+    );
+
+    let split_point = |name: &str| dto::Cursor {
+        values: Some(vec![dto::Value {
+            reference_value: Some(format!("projects/p/databases/(default)/documents/{}", name)),
+            ..Default::default()
+        }]),
+        before: None,
+    };
+    let partitions = to_partitions(vec![split_point("orders/b"), split_point("orders/a")]);
+    assert_eq!(partitions.len(), 3);
+    assert!(partitions[0].start_at.is_none());
+    assert!(partitions[2].end_before.is_none());
+
+    let structured_query = partitions[1].apply(query).structured_query();
+    let json = serde_json::to_value(&structured_query).unwrap();
+    assert_eq!(
+        json["startAt"],
+        serde_json::json!({
+            "values": [ { "referenceValue": "projects/p/databases/(default)/documents/orders/a" } ],
+            "before": true
+        })
+    );
+    assert_eq!(
+        json["endAt"]["values"][0]["referenceValue"],
+        "projects/p/databases/(default)/documents/orders/b"
+    );
+
+    assert_eq!(to_partitions(Vec::new()).len(), 1);
+}
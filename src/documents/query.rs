@@ -44,32 +44,123 @@ pub fn query(
     orderby_value: Option<Vec<(String, bool)>>,
 ) -> Result<Query> {
     let url = firebase_url_query(auth.project_id());
+    let query_request = query_request(collection_id, field_filter(where_value)?, orderby_value);
+    let json = run_query(auth, &url, &query_request, collection_id)?;
 
-    let mut structured_query = dto::StructuredQuery {
-        select: Some(dto::Projection { fields: None }),
-        order_by: None,
-        from: Some(vec![dto::CollectionSelector {
-            collection_id: Some(collection_id.to_owned()),
-            ..Default::default()
-        }]),
-        where_: None,
-        ..Default::default()
-    };
+    Ok(Query(json.into_iter()))
+}
+
+/// [Async] Query
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collectionid' The collection id; "my_collection" or "a/nested/collection"
+/// * 'select_value' The query / filter value. For example (value, field, operator)
+/// * 'orderby_value The order by value. For example array of ("field_1": true) for order by field_1 ascendingly, ("a_map.`000`": true) for orderby query start with numbers
+pub async fn query_async(
+    auth: &impl FirebaseAuthBearer,
+    collection_id: &str,
+    where_value: Option<(serde_json::Value, dto::FieldOperator, &str)>,
+    orderby_value: Option<Vec<(String, bool)>>,
+) -> Result<Query> {
+    let url = firebase_url_query(auth.project_id());
+    let query_request = query_request(collection_id, field_filter(where_value)?, orderby_value);
+    let json = run_query_async(auth, &url, &query_request, collection_id).await?;
+
+    Ok(Query(json.into_iter()))
+}
 
-    if let Some(wv) = where_value {
-        let (v, operator, field) = wv;
-        let value = crate::firebase_rest_to_rust::serde_value_to_firebase_value(&v);
-        structured_query.where_ = Some(dto::Filter {
+///
+/// Queries the database like [`query`], but with any filter. Filters are created via [`field`] and can
+/// be combined with a logical OR or AND, for example "status in [a, b] OR owner == me".
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{self, field};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let filter = field("status")
+///     .in_values(vec!["a".into(), "b".into()])?
+///     .or(field("owner").eq("me")?);
+/// for metadata in documents::query_where(&session, "tests", Some(filter), None)? {
+///     println!("id: {}", &metadata.name);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collectionid' The collection id; "my_collection" or "a/nested/collection"
+/// * 'filter' The query filter, see [`QueryFilter`]
+/// * 'orderby_value The order by value. For example array of ("field_1": true) for order by field_1 ascendingly
+pub fn query_where(
+    auth: &impl FirebaseAuthBearer,
+    collection_id: &str,
+    filter: Option<QueryFilter>,
+    orderby_value: Option<Vec<(String, bool)>>,
+) -> Result<Query> {
+    let url = firebase_url_query(auth.project_id());
+    let query_request = query_request(collection_id, filter.map(dto::Filter::from), orderby_value);
+    let json = run_query(auth, &url, &query_request, collection_id)?;
+
+    Ok(Query(json.into_iter()))
+}
+
+/// [Async] Queries the database like [`query_async`], but with any filter. See [`query_where`].
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'collectionid' The collection id; "my_collection" or "a/nested/collection"
+/// * 'filter' The query filter, see [`QueryFilter`]
+/// * 'orderby_value The order by value. For example array of ("field_1": true) for order by field_1 ascendingly
+pub async fn query_where_async(
+    auth: &impl FirebaseAuthBearer,
+    collection_id: &str,
+    filter: Option<QueryFilter>,
+    orderby_value: Option<Vec<(String, bool)>>,
+) -> Result<Query> {
+    let url = firebase_url_query(auth.project_id());
+    let query_request = query_request(collection_id, filter.map(dto::Filter::from), orderby_value);
+    let json = run_query_async(auth, &url, &query_request, collection_id).await?;
+
+    Ok(Query(json.into_iter()))
+}
+
+/// Creates the field filter of a (value, operator, field) tuple
+fn field_filter(where_value: Option<(serde_json::Value, dto::FieldOperator, &str)>) -> Result<Option<dto::Filter>> {
+    match where_value {
+        Some((v, operator, field)) => Ok(Some(dto::Filter {
             field_filter: Some(dto::FieldFilter {
-                value,
+                value: serde_value_to_firebase_value(&v)?,
                 op: operator,
                 field: dto::FieldReference {
                     field_path: field.to_owned(),
                 },
             }),
             ..Default::default()
-        });
+        })),
+        None => Ok(None),
     }
+}
+
+/// Creates the request of a [`query`] that only returns document names
+fn query_request(
+    collection_id: &str,
+    where_: Option<dto::Filter>,
+    orderby_value: Option<Vec<(String, bool)>>,
+) -> dto::RunQueryRequest {
+    let mut structured_query = dto::StructuredQuery {
+        select: Some(dto::Projection { fields: None }),
+        order_by: None,
+        from: Some(vec![dto::CollectionSelector {
+            collection_id: Some(collection_id.to_owned()),
+            ..Default::default()
+        }]),
+        where_,
+        ..Default::default()
+    };
 
     if let Some(ov) = orderby_value {
         let mut orders = vec![];
@@ -86,27 +177,41 @@ pub fn query(
         structured_query.order_by = Some(orders);
     }
 
-    let query_request = dto::RunQueryRequest {
+    dto::RunQueryRequest {
         structured_query: Some(structured_query),
         ..Default::default()
-    };
+    }
+}
 
+/// Executes the given query request and returns all responses.
+/// This is used for the runQuery and the runAggregationQuery endpoints.
+pub(super) fn run_query<REQUEST, RESPONSE>(
+    auth: &impl FirebaseAuthBearer,
+    url: &str,
+    query_request: &REQUEST,
+    context: &str,
+) -> Result<Vec<RESPONSE>>
+where
+    REQUEST: Serialize,
+    for<'b> RESPONSE: Deserialize<'b>,
+{
     let resp = exp_backoff(
         || {
             let resp = auth
                 .client()
-                .post(&url)
+                .post(url)
                 .bearer_auth(auth.access_token().to_owned())
-                .json(&query_request)
+                .json(query_request)
                 .send()
                 .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
 
             let status = resp.status().as_u16();
 
-            match extract_google_api_error(resp, || collection_id.to_owned()) {
+            match extract_google_api_error(resp, || context.to_owned()) {
                 Ok(new_resp) => Ok(new_resp),
                 Err(err) => {
-                    if retryable_http_status(status) {
+                    // 409 means that the transaction of the query has been aborted
+                    if retryable_http_status(status) && status != 409 {
                         Err(backoff::Error::Transient(err))
                     } else {
                         Err(backoff::Error::Permanent(err))
@@ -117,88 +222,41 @@ pub fn query(
         FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
     )?;
 
-    let json: Option<Vec<dto::RunQueryResponse>> = resp.json()?;
+    let json: Option<Vec<RESPONSE>> = resp.json()?;
 
-    Ok(Query(json.unwrap_or_default().into_iter()))
+    Ok(json.unwrap_or_default())
 }
 
-/// [Async] Query
-/// ## Arguments
-/// * 'auth' The authentication token
-/// * 'collectionid' The collection id; "my_collection" or "a/nested/collection"
-/// * 'select_value' The query / filter value. For example (value, field, operator)
-/// * 'orderby_value The order by value. For example array of ("field_1": true) for order by field_1 ascendingly, ("a_map.`000`": true) for orderby query start with numbers
-pub async fn query_async(
+/// [Async] Executes the given query request and returns all responses.
+/// This is used for the runQuery and the runAggregationQuery endpoints.
+pub(super) async fn run_query_async<REQUEST, RESPONSE>(
     auth: &impl FirebaseAuthBearer,
-    collection_id: &str,
-    where_value: Option<(serde_json::Value, dto::FieldOperator, &str)>,
-    orderby_value: Option<Vec<(String, bool)>>,
-) -> Result<Query> {
-    let url = firebase_url_query(auth.project_id());
-
-    let mut structured_query = dto::StructuredQuery {
-        select: Some(dto::Projection { fields: None }),
-        order_by: None,
-        from: Some(vec![dto::CollectionSelector {
-            collection_id: Some(collection_id.to_owned()),
-            ..Default::default()
-        }]),
-        where_: None,
-        ..Default::default()
-    };
-
-    if let Some(wv) = where_value {
-        let (v, operator, field) = wv;
-        let value = crate::firebase_rest_to_rust::serde_value_to_firebase_value(&v);
-        structured_query.where_ = Some(dto::Filter {
-            field_filter: Some(dto::FieldFilter {
-                value,
-                op: operator,
-                field: dto::FieldReference {
-                    field_path: field.to_owned(),
-                },
-            }),
-            ..Default::default()
-        });
-    }
-
-    if let Some(ov) = orderby_value {
-        let mut orders = vec![];
-        for (f, asc) in ov {
-            let mut o = dto::Order {
-                field: Some(dto::FieldReference {
-                    field_path: f.to_owned(),
-                }),
-                ..Default::default()
-            };
-            o.direction = if asc { None } else { Some("desc".to_owned()) };
-            orders.push(o);
-        }
-        structured_query.order_by = Some(orders);
-    }
-
-    let query_request = dto::RunQueryRequest {
-        structured_query: Some(structured_query),
-        ..Default::default()
-    };
-
+    url: &str,
+    query_request: &REQUEST,
+    context: &str,
+) -> Result<Vec<RESPONSE>>
+where
+    REQUEST: Serialize,
+    for<'b> RESPONSE: Deserialize<'b>,
+{
     let resp = exp_backoff_async(
         || async {
             let resp = auth
                 .client_async()
-                .post(&url)
+                .post(url)
                 .bearer_auth(auth.access_token().to_owned())
-                .json(&query_request)
+                .json(query_request)
                 .send()
                 .await
                 .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
 
             let status = resp.status().as_u16();
 
-            match extract_google_api_error_async(resp, || collection_id.to_owned()).await {
+            match extract_google_api_error_async(resp, || context.to_owned()).await {
                 Ok(new_resp) => Ok(new_resp),
                 Err(err) => {
-                    if retryable_http_status(status) {
+                    // 409 means that the transaction of the query has been aborted
+                    if retryable_http_status(status) && status != 409 {
                         Err(backoff::Error::Transient(err))
                     } else {
                         Err(backoff::Error::Permanent(err))
@@ -210,9 +268,9 @@ pub async fn query_async(
     )
     .await?;
 
-    let json: Option<Vec<dto::RunQueryResponse>> = resp.json().await?;
+    let json: Option<Vec<RESPONSE>> = resp.json().await?;
 
-    Ok(Query(json.unwrap_or_default().into_iter()))
+    Ok(json.unwrap_or_default())
 }
 
 /// This type is returned as a result by [`query`].
@@ -220,10 +278,11 @@ pub async fn query_async(
 ///
 /// If you just need the meta data like the document name or update time, you are already settled.
 /// To fetch the document itself, use [`read_by_name`].
+/// Queries of a [`QueryBuilder`] return full documents. Use [`QueryBuilder::run_typed`] to deserialize them directly.
 ///
 /// Please note that this API acts as an iterator of same-like documents.
 /// This type is not suitable if you want to list documents of different types.
-pub struct Query(IntoIter<dto::RunQueryResponse>);
+pub struct Query(pub(super) IntoIter<dto::RunQueryResponse>);
 
 impl Iterator for Query {
     type Item = dto::Document;
@@ -238,3 +297,34 @@ impl Iterator for Query {
         return None;
     }
 }
+
+/// This type is returned as a result by [`QueryBuilder::run_typed`].
+/// Use it as an iterator. In contrast to [`Query`], the documents are deserialized directly,
+/// without fetching them again.
+///
+/// Combine it with [`QueryBuilder::select`] to deserialize only some fields into a partial struct.
+pub struct TypedQuery<T> {
+    query: Query,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> TypedQuery<T> {
+    pub(super) fn new(query: Query) -> Self {
+        TypedQuery {
+            query,
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T> Iterator for TypedQuery<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    type Item = Result<DocumentSnapshot<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let doc = self.query.next()?;
+        Some(DocumentSnapshot::from_document(&doc))
+    }
+}
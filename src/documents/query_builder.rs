@@ -0,0 +1,671 @@
+use super::*;
+
+/// A reference to a document field, used to create [`QueryFilter`]s. See [`field`].
+///
+/// The filters that compare the field with a value fail if the value can not be converted to a Firestore value,
+/// for example an integer above `i64::max_value()`.
+#[derive(Clone, Debug)]
+pub struct QueryField(String);
+
+///
+/// Refers to a document field within a query. Nested fields are separated by a dot, for example "a_map.a".
+///
+/// Use the returned [`QueryField`] to create filters for a [`QueryBuilder`], for example `field("type").eq("suv")?`.
+pub fn field(field_path: impl Into<String>) -> QueryField {
+    QueryField(field_path.into())
+}
+
+impl QueryField {
+    fn filter(self, op: dto::FieldOperator, value: serde_json::Value) -> Result<QueryFilter> {
+        Ok(QueryFilter {
+            filter: dto::Filter {
+                field_filter: Some(dto::FieldFilter {
+                    field: dto::FieldReference { field_path: self.0 },
+                    value: value_to_firebase(&value)?,
+                    op,
+                }),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// The field is equal to the given value
+    pub fn eq(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::EQUAL, value.into())
+    }
+
+    /// The field is less than the given value
+    pub fn lt(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::LESS_THAN, value.into())
+    }
+
+    /// The field is less than or equal to the given value
+    pub fn lte(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::LESS_THAN_OR_EQUAL, value.into())
+    }
+
+    /// The field is greater than the given value
+    pub fn gt(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::GREATER_THAN, value.into())
+    }
+
+    /// The field is greater than or equal to the given value
+    pub fn gte(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::GREATER_THAN_OR_EQUAL, value.into())
+    }
+
+    /// The field is an array that contains the given value
+    pub fn array_contains(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::ARRAY_CONTAINS, value.into())
+    }
+
+    /// The field is equal to one of the given values
+    pub fn in_values(self, values: Vec<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::IN, serde_json::Value::Array(values))
+    }
+
+    /// The field exists and is not equal to the given value
+    pub fn neq(self, value: impl Into<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::NOT_EQUAL, value.into())
+    }
+
+    /// The field is an array that contains at least one of the given values
+    pub fn array_contains_any(self, values: Vec<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::ARRAY_CONTAINS_ANY, serde_json::Value::Array(values))
+    }
+
+    /// The field exists and is not equal to any of the given values
+    pub fn not_in(self, values: Vec<serde_json::Value>) -> Result<QueryFilter> {
+        self.filter(dto::FieldOperator::NOT_IN, serde_json::Value::Array(values))
+    }
+
+    fn unary_filter(self, op: dto::UnaryOperator) -> QueryFilter {
+        QueryFilter {
+            filter: dto::Filter {
+                unary_filter: Some(dto::UnaryFilter {
+                    field: dto::FieldReference { field_path: self.0 },
+                    op,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The field is null
+    pub fn is_null(self) -> QueryFilter {
+        self.unary_filter(dto::UnaryOperator::IS_NULL)
+    }
+
+    /// The field exists and is not null
+    pub fn is_not_null(self) -> QueryFilter {
+        self.unary_filter(dto::UnaryOperator::IS_NOT_NULL)
+    }
+
+    /// The field is a double with the value NaN
+    pub fn is_nan(self) -> QueryFilter {
+        self.unary_filter(dto::UnaryOperator::IS_NAN)
+    }
+
+    /// The field exists and is not NaN
+    pub fn is_not_nan(self) -> QueryFilter {
+        self.unary_filter(dto::UnaryOperator::IS_NOT_NAN)
+    }
+}
+
+///
+/// A query filter, created via [`field`]. Use it with [`QueryBuilder::filter`].
+///
+/// Filters can be combined with [`QueryFilter::and`] and [`QueryFilter::or`], or with [`all_of`] and [`any_of`].
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{field, QueryBuilder};
+///
+/// // status in [a, b] OR owner == me
+/// let query = QueryBuilder::collection("tasks")
+///     .filter(field("status").in_values(vec!["a".into(), "b".into()])?.or(field("owner").eq("me")?));
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct QueryFilter {
+    filter: dto::Filter,
+}
+
+impl QueryFilter {
+    /// Matches documents that match this filter and the given filter
+    pub fn and(self, other: QueryFilter) -> QueryFilter {
+        all_of(vec![self, other])
+    }
+
+    /// Matches documents that match this filter or the given filter
+    pub fn or(self, other: QueryFilter) -> QueryFilter {
+        any_of(vec![self, other])
+    }
+}
+
+/// Matches documents that match all of the given filters
+pub fn all_of(filters: Vec<QueryFilter>) -> QueryFilter {
+    combine_filters(filters, dto::CompositeOperator::AND)
+}
+
+/// Matches documents that match at least one of the given filters
+pub fn any_of(filters: Vec<QueryFilter>) -> QueryFilter {
+    combine_filters(filters, dto::CompositeOperator::OR)
+}
+
+fn combine_filters(filters: Vec<QueryFilter>, op: dto::CompositeOperator) -> QueryFilter {
+    let filters = filters.into_iter().map(|filter| filter.filter).collect();
+    QueryFilter {
+        filter: composite_filter(filters, op),
+    }
+}
+
+/// Combines the given filters with the given operator. Nested filters with the same operator are flattened
+/// and a single filter is returned as it is.
+fn composite_filter(filters: Vec<dto::Filter>, op: dto::CompositeOperator) -> dto::Filter {
+    let mut flattened = Vec::with_capacity(filters.len());
+    for filter in filters {
+        match filter.composite_filter {
+            Some(composite) if composite.op == op => flattened.extend(composite.filters),
+            composite_filter => flattened.push(dto::Filter {
+                composite_filter,
+                ..filter
+            }),
+        }
+    }
+    if flattened.len() == 1 {
+        return flattened.remove(0);
+    }
+    dto::Filter {
+        composite_filter: Some(dto::CompositeFilter { filters: flattened, op }),
+        ..Default::default()
+    }
+}
+
+impl From<QueryFilter> for dto::Filter {
+    fn from(filter: QueryFilter) -> Self {
+        filter.filter
+    }
+}
+
+///
+/// Builds a structured query with any number of filters, an ordering, cursors, a limit and a projection.
+///
+/// All filters are combined with a logical AND. Use [`QueryFilter::or`] or [`any_of`] for alternatives.
+/// The query is compiled down to a [`dto::StructuredQuery`] and executed with [`QueryBuilder::run`]
+/// or [`QueryBuilder::run_async`].
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{field, QueryBuilder};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let results = QueryBuilder::collection("cars")
+///     .filter(field("type").eq("suv")?)
+///     .and(field("price").lt(100)?)
+///     .order_by("price", false)
+///     .limit(20)
+///     .run(&session)?;
+/// for document in results {
+///     println!("{}", document.name);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct QueryBuilder {
+    parent: String,
+    collection_id: String,
+    all_descendants: bool,
+    filters: Vec<dto::Filter>,
+    order_by: Vec<dto::Order>,
+    start_at: Option<dto::Cursor>,
+    end_at: Option<dto::Cursor>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    select: Option<Vec<String>>,
+    transaction: Option<String>,
+    read_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl QueryBuilder {
+    /// Creates a query for all documents of the given collection
+    ///
+    /// ## Arguments
+    /// * 'collection_id' The collection id; "my_collection" or "a/nested/collection"
+    pub fn collection(collection_id: impl Into<String>) -> Self {
+        let collection_id = collection_id.into();
+        match collection_id.rfind('/') {
+            Some(index) => QueryBuilder {
+                parent: collection_id[..index].to_owned(),
+                collection_id: collection_id[index + 1..].to_owned(),
+                ..Default::default()
+            },
+            None => QueryBuilder {
+                collection_id,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Creates a collection group query. It includes the documents of all collections with the given id,
+    /// no matter where they are nested. For example all "reviews" of any "products/*" document.
+    ///
+    /// Use [`QueryBuilder::parent`] to only include collections below a specific document.
+    ///
+    /// ## Arguments
+    /// * 'collection_id' The collection id, for example "reviews"
+    pub fn collection_group(collection_id: impl Into<String>) -> Self {
+        QueryBuilder {
+            collection_id: collection_id.into(),
+            all_descendants: true,
+            ..Default::default()
+        }
+    }
+
+    /// Scopes the query to the collections below the given parent document.
+    ///
+    /// ## Arguments
+    /// * 'parent' The document path, for example "users/u1".
+    ///   An absolute document name like "projects/{project_id}/databases/(default)/documents/users/u1" is accepted as well.
+    pub fn parent(mut self, parent: impl Into<String>) -> Self {
+        self.parent = parent.into();
+        self
+    }
+
+    /// Adds a filter. Create filters via [`field`], for example `field("type").eq("suv")?`.
+    pub fn filter(mut self, filter: QueryFilter) -> Self {
+        self.filters.push(filter.filter);
+        self
+    }
+
+    /// Adds another filter. All filters of a query must match. This is an alias for [`QueryBuilder::filter`].
+    pub fn and(self, filter: QueryFilter) -> Self {
+        self.filter(filter)
+    }
+
+    /// Orders the results by the given field. Can be called multiple times to order by several fields.
+    ///
+    /// ## Arguments
+    /// * 'field_path' The field, for example "age" or "a_map.`000`" for fields that start with numbers
+    /// * 'ascending' Order ascendingly if true and descendingly otherwise
+    pub fn order_by(mut self, field_path: impl Into<String>, ascending: bool) -> Self {
+        self.order_by.push(dto::Order {
+            field: Some(dto::FieldReference {
+                field_path: field_path.into(),
+            }),
+            direction: if ascending { None } else { Some("DESCENDING".to_owned()) },
+        });
+        self
+    }
+
+    /// Returns at most the given number of documents. Limits above `i32::max_value()` are clamped.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(clamp_to_i32(limit));
+        self
+    }
+
+    /// Skips the given number of documents. Offsets above `i32::max_value()` are clamped.
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(clamp_to_i32(offset));
+        self
+    }
+
+    /// Only returns the given fields of each document. An empty list returns the document names only.
+    pub fn select(mut self, field_paths: &[&str]) -> Self {
+        // An empty projection would return all fields
+        self.select = Some(if field_paths.is_empty() {
+            vec!["__name__".to_owned()]
+        } else {
+            field_paths.iter().map(|&f| f.to_owned()).collect()
+        });
+        self
+    }
+
+    /// Starts at the document with the given values of the ordered fields, including that document.
+    /// The values must match the [`QueryBuilder::order_by`] clauses. Fails if a value can not be converted.
+    pub fn start_at(mut self, values: Vec<serde_json::Value>) -> Result<Self> {
+        self.start_at = Self::cursor(values, true)?;
+        Ok(self)
+    }
+
+    /// Starts after the document with the given values of the ordered fields.
+    /// The values must match the [`QueryBuilder::order_by`] clauses. Fails if a value can not be converted.
+    pub fn start_after(mut self, values: Vec<serde_json::Value>) -> Result<Self> {
+        self.start_at = Self::cursor(values, false)?;
+        Ok(self)
+    }
+
+    /// Ends at the document with the given values of the ordered fields, including that document.
+    /// The values must match the [`QueryBuilder::order_by`] clauses. Fails if a value can not be converted.
+    pub fn end_at(mut self, values: Vec<serde_json::Value>) -> Result<Self> {
+        self.end_at = Self::cursor(values, false)?;
+        Ok(self)
+    }
+
+    /// Ends before the document with the given values of the ordered fields.
+    /// The values must match the [`QueryBuilder::order_by`] clauses. Fails if a value can not be converted.
+    pub fn end_before(mut self, values: Vec<serde_json::Value>) -> Result<Self> {
+        self.end_at = Self::cursor(values, true)?;
+        Ok(self)
+    }
+
+    /// Converts the given cursor values. Fails if a value can not be converted to a Firestore value.
+    fn cursor(values: Vec<serde_json::Value>, before: bool) -> Result<Option<dto::Cursor>> {
+        Ok(Some(dto::Cursor {
+            values: Some(values.iter().map(value_to_firebase).collect::<Result<_>>()?),
+            before: Some(before),
+        }))
+    }
+
+    /// Replaces the start and end cursors, for example with the cursors of a [`QueryPartition`]
+    pub(super) fn cursors(mut self, start_at: Option<dto::Cursor>, end_at: Option<dto::Cursor>) -> Self {
+        self.start_at = start_at;
+        self.end_at = end_at;
+        self
+    }
+
+    /// Runs the query within a transaction or at a read time, see [`ReadOptions`].
+    /// A field mask is applied like [`QueryBuilder::select`].
+    pub fn read_options(mut self, options: ReadOptions) -> Self {
+        if let Some(mask) = &options.mask {
+            self = self.select(&mask.iter().map(|f| f.as_str()).collect::<Vec<_>>());
+        }
+        self.transaction = options.transaction;
+        self.read_time = options.read_time;
+        self
+    }
+
+    /// Compiles this query down to a [`dto::StructuredQuery`].
+    pub fn structured_query(&self) -> dto::StructuredQuery {
+        let where_ = if self.filters.is_empty() {
+            None
+        } else {
+            Some(composite_filter(self.filters.clone(), dto::CompositeOperator::AND))
+        };
+
+        dto::StructuredQuery {
+            select: self.select.as_ref().map(|fields| dto::Projection {
+                fields: Some(
+                    fields
+                        .iter()
+                        .map(|f| dto::FieldReference {
+                            field_path: f.to_owned(),
+                        })
+                        .collect(),
+                ),
+            }),
+            from: Some(vec![dto::CollectionSelector {
+                collection_id: Some(self.collection_id.clone()),
+                all_descendants: if self.all_descendants { Some(true) } else { None },
+            }]),
+            where_,
+            order_by: if self.order_by.is_empty() {
+                None
+            } else {
+                Some(self.order_by.clone())
+            },
+            start_at: self.start_at.clone(),
+            end_at: self.end_at.clone(),
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+
+    /// The id of the queried collection(s)
+    pub(super) fn collection_id(&self) -> &str {
+        &self.collection_id
+    }
+
+    /// The url of the runQuery endpoint of the parent document
+    pub(super) fn url(&self, project_id: &str) -> String {
+        self.action_url(project_id, "runQuery")
+    }
+
+    /// The url of the given endpoint of the parent document, for example "runAggregationQuery"
+    pub(super) fn action_url(&self, project_id: &str, action: &str) -> String {
+        if self.parent.is_empty() {
+            firebase_url_action(project_id, action)
+        } else if self.parent.starts_with("projects/") {
+            format!("{}:{}", firebase_url_base(&self.parent), action)
+        } else {
+            format!(
+                "{}:{}",
+                firebase_url_base(&document_name(project_id, &self.parent)),
+                action
+            )
+        }
+    }
+
+    pub(super) fn query_request(&self) -> dto::RunQueryRequest {
+        dto::RunQueryRequest {
+            structured_query: Some(self.structured_query()),
+            transaction: self.transaction.clone(),
+            read_time: self
+                .read_time
+                .map(|read_time| read_time.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)),
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Executes this query. The returned [`Query`] iterates over the matching documents.
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    pub fn run(&self, auth: &impl FirebaseAuthBearer) -> Result<Query> {
+        let url = self.url(auth.project_id());
+        let json = run_query(auth, &url, &self.query_request(), &self.collection_id)?;
+        Ok(Query(json.into_iter()))
+    }
+
+    ///
+    /// [Async] Executes this query. The returned [`Query`] iterates over the matching documents.
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    pub async fn run_async(&self, auth: &impl FirebaseAuthBearer) -> Result<Query> {
+        let url = self.url(auth.project_id());
+        let json = run_query_async(auth, &url, &self.query_request(), &self.collection_id).await?;
+        Ok(Query(json.into_iter()))
+    }
+
+    ///
+    /// Executes this query and deserializes the matching documents.
+    ///
+    /// Example:
+    /// ```rust
+    /// # use serde::{Serialize, Deserialize};
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct DemoDTO { a_string: String, an_int: u32, }
+    ///
+    /// /// Only some fields, used together with a projection
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct DemoPartialDTO { an_int: u32, }
+    ///
+    /// use firestore_db_and_auth::documents::{field, QueryBuilder, TypedQuery};
+    /// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+    ///
+    /// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+    ///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+    /// # let session = ServiceSession::new(credentials)?;
+    ///
+    /// let query = QueryBuilder::collection("tests").filter(field("a_string").eq("abc")?);
+    /// let values: TypedQuery<DemoDTO> = query.run_typed(&session)?;
+    /// for doc_result in values {
+    ///     // A snapshot is returned on success with the document data and metadata
+    ///     // like .id(), .create_time and .update_time
+    ///     let snapshot = doc_result?;
+    ///     println!("{}: {:?}", snapshot.id(), snapshot.data);
+    /// }
+    ///
+    /// let values: TypedQuery<DemoPartialDTO> = query.select(&["an_int"]).run_typed(&session)?;
+    /// for doc_result in values {
+    ///     if let Some(doc) = doc_result?.data {
+    ///         println!("{}", doc.an_int);
+    ///     }
+    /// }
+    /// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+    /// ```
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    pub fn run_typed<T>(&self, auth: &impl FirebaseAuthBearer) -> Result<TypedQuery<T>>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        Ok(TypedQuery::new(self.run(auth)?))
+    }
+
+    ///
+    /// [Async] Executes this query and deserializes the matching documents.
+    ///
+    /// ## Arguments
+    /// * 'auth' The authentication token
+    pub async fn run_typed_async<T>(&self, auth: &impl FirebaseAuthBearer) -> Result<TypedQuery<T>>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        Ok(TypedQuery::new(self.run_async(auth).await?))
+    }
+}
+
+impl Query {
+    /// Creates a [`QueryBuilder`] for all documents of the given collection.
+    /// This is a shortcut for [`QueryBuilder::collection`].
+    pub fn collection(collection_id: impl Into<String>) -> QueryBuilder {
+        QueryBuilder::collection(collection_id)
+    }
+}
+
+/// Converts a count into the i32 of the REST API. A plain cast would wrap larger counts into negative ones.
+pub(super) fn clamp_to_i32(count: u32) -> i32 {
+    count.min(i32::max_value() as u32) as i32
+}
+
+#[test]
+fn collection_group_test() {
+    let query = QueryBuilder::collection_group("reviews").parent("products/p1");
+    assert_eq!(
+        query.url("p"),
+        "https://firestore.googleapis.com/v1/projects/p/databases/(default)/documents/products/p1:runQuery"
+    );
+    let json = serde_json::to_value(query.structured_query()).unwrap();
+    assert_eq!(
+        json["from"],
+        serde_json::json!([{ "allDescendants": true, "collectionId": "reviews" }])
+    );
+
+    let query = QueryBuilder::collection("users/u1/orders");
+    assert_eq!(
+        query.url("p"),
+        "https://firestore.googleapis.com/v1/projects/p/databases/(default)/documents/users/u1:runQuery"
+    );
+}
+
+#[test]
+fn query_builder_test() {
+    let query = QueryBuilder::collection("cars")
+        .filter(field("type").eq("suv").unwrap())
+        .and(field("price").lt(100).unwrap())
+        .order_by("price", false)
+        .limit(20)
+        .start_after(vec![50.into()])
+        .unwrap()
+        .select(&["type", "price"])
+        .structured_query();
+    let json = serde_json::to_value(&query).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "select": { "fields": [ { "fieldPath": "type" }, { "fieldPath": "price" } ] },
+            "from": [ { "allDescendants": null, "collectionId": "cars" } ],
+            "where": {
+                "compositeFilter": {
+                    "op": "AND",
+                    "filters": [
+                        { "fieldFilter": { "field": { "fieldPath": "type" }, "op": "EQUAL", "value": { "stringValue": "suv" } } },
+                        { "fieldFilter": { "field": { "fieldPath": "price" }, "op": "LESS_THAN", "value": { "integerValue": "100" } } }
+                    ]
+                }
+            },
+            "orderBy": [ { "field": { "fieldPath": "price" }, "direction": "DESCENDING" } ],
+            "startAt": { "values": [ { "integerValue": "50" } ], "before": false },
+            "limit": 20
+        })
+    );
+
+    let query = QueryBuilder::collection("cars")
+        .limit(u32::max_value())
+        .offset(u32::max_value())
+        .structured_query();
+    assert_eq!(query.limit, Some(i32::max_value()));
+    assert_eq!(query.offset, Some(i32::max_value()));
+}
+
+#[test]
+fn query_read_options_test() {
+    let request = QueryBuilder::collection("cars")
+        .read_options(ReadOptions {
+            mask: Some(vec!["type".to_owned()]),
+            read_time: Some("2020-01-22T10:00:00Z".parse().unwrap()),
+            ..Default::default()
+        })
+        .query_request();
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["readTime"], "2020-01-22T10:00:00.000000000Z");
+    assert!(json.get("transaction").is_none());
+    assert_eq!(json["structuredQuery"]["select"]["fields"][0]["fieldPath"], "type");
+}
+
+#[test]
+fn query_filter_test() {
+    let query = QueryBuilder::collection("tasks")
+        .filter(
+            field("status")
+                .in_values(vec!["a".into(), "b".into()])
+                .unwrap()
+                .or(field("owner").eq("me").unwrap())
+                .or(field("shared").is_not_null()),
+        )
+        .and(field("deleted").neq(true).unwrap().and(field("score").is_not_nan()))
+        .structured_query();
+    let json = serde_json::to_value(&query.where_).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "compositeFilter": {
+                "op": "AND",
+                "filters": [
+                    {
+                        "compositeFilter": {
+                            "op": "OR",
+                            "filters": [
+                                { "fieldFilter": { "field": { "fieldPath": "status" }, "op": "IN", "value": { "arrayValue": { "values": [ { "stringValue": "a" }, { "stringValue": "b" } ] } } } },
+                                { "fieldFilter": { "field": { "fieldPath": "owner" }, "op": "EQUAL", "value": { "stringValue": "me" } } },
+                                { "unaryFilter": { "field": { "fieldPath": "shared" }, "op": "IS_NOT_NULL" } }
+                            ]
+                        }
+                    },
+                    { "fieldFilter": { "field": { "fieldPath": "deleted" }, "op": "NOT_EQUAL", "value": { "booleanValue": true } } },
+                    { "unaryFilter": { "field": { "fieldPath": "score" }, "op": "IS_NOT_NAN" } }
+                ]
+            }
+        })
+    );
+
+    let filter = dto::Filter::from(any_of(vec![field("a").is_null()]));
+    assert!(filter.unary_filter.is_some());
+
+    // Values that can not be converted fail the filter instead of being sent empty
+    let too_large = serde_json::Value::from(u64::max_value());
+    match field("b").eq(too_large.clone()) {
+        Err(FirebaseError::Ser { .. }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    let query = QueryBuilder::collection("tasks").order_by("a", true);
+    assert!(query.start_at(vec![too_large]).is_err());
+}
@@ -1,5 +1,55 @@
 use super::*;
 
+/// Options for reading documents, for example with [`read_with_options`] or [`batch_read`].
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    /// Only return the given fields of each document. Nested fields are separated by a dot, for example "a_map.a".
+    pub mask: Option<Vec<String>>,
+    /// Read the documents within the transaction with the given id, see [`Transaction::id`].
+    pub transaction: Option<String>,
+    /// Read the documents as they were at the given time. Use the same read time for multiple requests
+    /// to get a consistent snapshot, or read older versions of documents with point-in-time recovery.
+    /// This can not be combined with a transaction.
+    pub read_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ReadOptions {
+    pub(super) fn document_mask(&self) -> Option<dto::DocumentMask> {
+        self.mask.as_ref().map(|field_paths| dto::DocumentMask {
+            field_paths: field_paths.clone(),
+        })
+    }
+
+    pub(super) fn read_time_string(&self) -> Option<String> {
+        self.read_time
+            .map(|read_time| read_time.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true))
+    }
+
+    /// The options as url query parameters of a document read
+    pub(super) fn query_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        for field_path in self.mask.iter().flatten() {
+            params.push(("mask.fieldPaths", field_path.clone()));
+        }
+        if let Some(transaction) = &self.transaction {
+            params.push(("transaction", transaction.clone()));
+        }
+        if let Some(read_time) = self.read_time_string() {
+            params.push(("readTime", read_time));
+        }
+        params
+    }
+}
+
+/// This type is returned as a result by [`batch_read`].
+#[derive(Debug)]
+pub struct BatchRead<T> {
+    /// The found documents
+    pub found: Vec<DocumentSnapshot<T>>,
+    /// The absolute document names of the documents that do not exist
+    pub missing: Vec<String>,
+}
+
 ///
 /// Read a document of a specific type from a collection by its Firestore document name
 ///
@@ -10,23 +60,386 @@ pub fn read_by_name<T>(auth: &impl FirebaseAuthBearer, document_name: impl AsRef
 where
     for<'b> T: Deserialize<'b>,
 {
-    let url = firebase_url_base(document_name.as_ref());
+    let json = get_document(auth, document_name.as_ref(), &[])?;
+    Ok(document_to_pod(&json)?)
+}
+
+///
+/// [Async] Read a document of a specific type from a collection by its Firestore document name
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'document_name' The document path / collection and document id; For example "projects/my_project/databases/(default)/documents/tests/test"
+pub async fn read_by_name_async<T>(auth: &impl FirebaseAuthBearer, document_name: impl AsRef<str>) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let json = get_document_async(auth, document_name.as_ref(), &[]).await?;
+    Ok(document_to_pod(&json)?)
+}
+
+///
+/// Read a document of a specific type from a collection
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+pub fn read<T>(auth: &impl FirebaseAuthBearer, path: &str, document_id: impl AsRef<str>) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let document_name = format!(
+        "projects/{}/databases/(default)/documents/{}/{}",
+        auth.project_id(),
+        path,
+        document_id.as_ref()
+    );
+    read_by_name(auth, &document_name)
+}
+
+///
+/// [Async] Read a document of a specific type from a collection
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+pub async fn read_async<T>(auth: &impl FirebaseAuthBearer, path: &str, document_id: impl AsRef<str>) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let document_name = format!(
+        "projects/{}/databases/(default)/documents/{}/{}",
+        auth.project_id(),
+        path,
+        document_id.as_ref()
+    );
+    read_by_name_async(auth, &document_name).await
+}
+
+///
+/// Read a document of a specific type from a collection, if it exists.
+///
+/// In contrast to [`read`], a missing document is not an error, but returns None.
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// match documents::read_opt::<DemoDTO>(&session, "tests", "non_existing")? {
+///     Some(doc) => println!("{:?}", doc),
+///     None => println!("The document does not exist"),
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+pub fn read_opt<T>(auth: &impl FirebaseAuthBearer, path: &str, document_id: impl AsRef<str>) -> Result<Option<T>>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    Ok(read_snapshot(auth, path, document_id, ReadOptions::default())?.data)
+}
+
+///
+/// [Async] Read a document of a specific type from a collection, if it exists.
+///
+/// In contrast to [`read_async`], a missing document is not an error, but returns None.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+pub async fn read_opt_async<T>(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+) -> Result<Option<T>>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    Ok(read_snapshot_async(auth, path, document_id, ReadOptions::default())
+        .await?
+        .data)
+}
+
+/// The query parameters of a read that returns the document name, but no fields
+#[inline]
+fn exists_params() -> [(&'static str, String); 1] {
+    [("mask.fieldPaths", "__name__".to_owned())]
+}
+
+///
+/// Check if a document exists. No fields of the document are fetched.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// if !documents::exists(&session, "tests/non_existing")? {
+///     println!("The document does not exist");
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+pub fn exists(auth: &impl FirebaseAuthBearer, path: &str) -> Result<bool> {
+    match get_document(auth, &document_name(auth.project_id(), path), &exists_params()) {
+        Ok(_) => Ok(true),
+        Err(FirebaseError::APIError(404, _, _)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+///
+/// [Async] Check if a document exists. No fields of the document are fetched.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+pub async fn exists_async(auth: &impl FirebaseAuthBearer, path: &str) -> Result<bool> {
+    match get_document_async(auth, &document_name(auth.project_id(), path), &exists_params()).await {
+        Ok(_) => Ok(true),
+        Err(FirebaseError::APIError(404, _, _)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+///
+/// Read a document of a specific type from a collection, with the given read options.
+///
+/// Use a field mask to only fetch some fields and deserialize them into a small projection struct.
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// /// Only some fields of the document
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoPartialDTO { an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let options = documents::ReadOptions { mask: Some(vec!["an_int".to_owned()]), ..Default::default() };
+/// let doc: DemoPartialDTO = documents::read_with_options(&session, "tests", "service_test", options)?;
+/// println!("{}", doc.an_int);
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+/// * 'options' A field mask, a transaction or a read time
+pub fn read_with_options<T>(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+    options: ReadOptions,
+) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let document_name = document_name(auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+    read_by_name_with_options(auth, &document_name, options)
+}
+
+///
+/// [Async] Read a document of a specific type from a collection, with the given read options.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+/// * 'options' A field mask, a transaction or a read time
+pub async fn read_with_options_async<T>(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+    options: ReadOptions,
+) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let document_name = document_name(auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+    read_by_name_with_options_async(auth, &document_name, options).await
+}
+
+///
+/// Read a document of a specific type by its Firestore document name, with the given read options.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'document_name' The document path / collection and document id; For example "projects/my_project/databases/(default)/documents/tests/test"
+/// * 'options' A field mask, a transaction or a read time
+pub fn read_by_name_with_options<T>(
+    auth: &impl FirebaseAuthBearer,
+    document_name: impl AsRef<str>,
+    options: ReadOptions,
+) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let json = get_document(auth, document_name.as_ref(), &options.query_params())?;
+    Ok(document_to_pod(&json)?)
+}
+
+///
+/// [Async] Read a document of a specific type by its Firestore document name, with the given read options.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'document_name' The document path / collection and document id; For example "projects/my_project/databases/(default)/documents/tests/test"
+/// * 'options' A field mask, a transaction or a read time
+pub async fn read_by_name_with_options_async<T>(
+    auth: &impl FirebaseAuthBearer,
+    document_name: impl AsRef<str>,
+    options: ReadOptions,
+) -> Result<T>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let json = get_document_async(auth, document_name.as_ref(), &options.query_params()).await?;
+    Ok(document_to_pod(&json)?)
+}
+
+///
+/// Read a document of a specific type from a collection, together with its metadata.
+///
+/// In contrast to [`read`], a missing document is not an error.
+/// The returned [`DocumentSnapshot`] reports it via [`DocumentSnapshot::exists`].
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let snapshot: documents::DocumentSnapshot<DemoDTO> =
+///     documents::read_snapshot(&session, "tests", "service_test", documents::ReadOptions::default())?;
+/// if snapshot.exists() {
+///     println!("{} updated at {:?}: {:?}", snapshot.id(), snapshot.update_time, snapshot.data);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+/// * 'options' A field mask, a transaction or a read time
+pub fn read_snapshot<T>(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+    options: ReadOptions,
+) -> Result<DocumentSnapshot<T>>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let document_name = document_name(auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+    match get_document(auth, &document_name, &options.query_params()) {
+        Ok(json) => DocumentSnapshot::from_document(&json),
+        Err(FirebaseError::APIError(404, _, _)) => Ok(DocumentSnapshot::missing(document_name)),
+        Err(err) => Err(err),
+    }
+}
+
+///
+/// [Async] Read a document of a specific type from a collection, together with its metadata.
+///
+/// In contrast to [`read_async`], a missing document is not an error.
+/// The returned [`DocumentSnapshot`] reports it via [`DocumentSnapshot::exists`].
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+/// * 'options' A field mask, a transaction or a read time
+pub async fn read_snapshot_async<T>(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+    options: ReadOptions,
+) -> Result<DocumentSnapshot<T>>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let document_name = document_name(auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+    match get_document_async(auth, &document_name, &options.query_params()).await {
+        Ok(json) => DocumentSnapshot::from_document(&json),
+        Err(FirebaseError::APIError(404, _, _)) => Ok(DocumentSnapshot::missing(document_name)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Fetch the raw document with the given absolute document name.
+/// The given query parameters are appended to the request url, for example a transaction id.
+pub(super) fn get_document(
+    auth: &impl FirebaseAuthBearer,
+    document_name: &str,
+    params: &[(&str, String)],
+) -> Result<dto::Document> {
+    get_document_with(auth, document_name, params, |_, err| err)
+}
+
+/// Fetches like [`get_document`]. A Google API error is handed to `map_error` together with its status.
+pub(super) fn get_document_with(
+    auth: &impl FirebaseAuthBearer,
+    document_name: &str,
+    params: &[(&str, String)],
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
+) -> Result<dto::Document> {
+    let url = firebase_url_base(document_name);
 
     let resp = exp_backoff(
         || {
             let resp = auth
                 .client()
                 .get(&url)
+                .query(params)
                 .bearer_auth(auth.access_token().to_owned())
                 .send()
                 .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
 
             let status = resp.status().as_u16();
 
-            match extract_google_api_error(resp, || document_name.as_ref().to_owned()) {
+            match extract_google_api_error_with(resp, || document_name.to_owned(), &map_error) {
                 Ok(new_resp) => Ok(new_resp),
                 Err(err) => {
-                    if retryable_http_status(status) {
+                    // An aborted read within a transaction is not retried. The entire transaction is retried instead.
+                    if retryable_http_status(status) && status != 409 {
                         Err(backoff::Error::Transient(err))
                     } else {
                         Err(backoff::Error::Permanent(err))
@@ -38,26 +451,34 @@ where
     )?;
 
     let json: dto::Document = resp.json()?;
-    Ok(document_to_pod(&json)?)
+    Ok(json)
 }
 
-///
-/// [Async] Read a document of a specific type from a collection by its Firestore document name
-///
-/// ## Arguments
-/// * 'auth' The authentication token
-/// * 'document_name' The document path / collection and document id; For example "projects/my_project/databases/(default)/documents/tests/test"
-pub async fn read_by_name_async<T>(auth: &impl FirebaseAuthBearer, document_name: impl AsRef<str>) -> Result<T>
-where
-    for<'b> T: Deserialize<'b>,
-{
-    let url = firebase_url_base(document_name.as_ref());
+/// [Async] Fetch the raw document with the given absolute document name.
+/// The given query parameters are appended to the request url, for example a transaction id.
+pub(super) async fn get_document_async(
+    auth: &impl FirebaseAuthBearer,
+    document_name: &str,
+    params: &[(&str, String)],
+) -> Result<dto::Document> {
+    get_document_with_async(auth, document_name, params, |_, err| err).await
+}
+
+/// [Async] Fetches like [`get_document_async`]. A Google API error is handed to `map_error` together with its status.
+pub(super) async fn get_document_with_async(
+    auth: &impl FirebaseAuthBearer,
+    document_name: &str,
+    params: &[(&str, String)],
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
+) -> Result<dto::Document> {
+    let url = firebase_url_base(document_name);
 
     let resp = exp_backoff_async(
         || async {
             let resp = auth
                 .client_async()
                 .get(&url)
+                .query(params)
                 .bearer_auth(auth.access_token().to_owned())
                 .send()
                 .await
@@ -65,10 +486,11 @@ where
 
             let status = resp.status().as_u16();
 
-            match extract_google_api_error_async(resp, || document_name.as_ref().to_owned()).await {
+            match extract_google_api_error_with_async(resp, || document_name.to_owned(), &map_error).await {
                 Ok(new_resp) => Ok(new_resp),
                 Err(err) => {
-                    if retryable_http_status(status) {
+                    // An aborted read within a transaction is not retried. The entire transaction is retried instead.
+                    if retryable_http_status(status) && status != 409 {
                         Err(backoff::Error::Transient(err))
                     } else {
                         Err(backoff::Error::Permanent(err))
@@ -81,45 +503,196 @@ where
     .await?;
 
     let json: dto::Document = resp.json().await?;
-    Ok(document_to_pod(&json)?)
+    Ok(json)
 }
 
 ///
-/// Read a document of a specific type from a collection
+/// Read multiple documents of a specific type with a single request.
+///
+/// The order of the results does not necessarily match the order of the given paths.
+/// Documents that do not exist are reported in [`BatchRead::missing`].
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct DemoDTO { a_string: String, an_int: u32, }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let result = documents::batch_read::<DemoDTO>(&session, &["tests/a", "tests/b"], documents::ReadOptions::default())?;
+/// for snapshot in result.found {
+///     println!("{}: {:?}", snapshot.id(), snapshot.data);
+/// }
+/// for name in result.missing {
+///     println!("{} does not exist", name);
+/// }
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
 ///
 /// ## Arguments
 /// * 'auth' The authentication token
-/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
-/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
-pub fn read<T>(auth: &impl FirebaseAuthBearer, path: &str, document_id: impl AsRef<str>) -> Result<T>
+/// * 'paths' The document paths; For example "my_collection/document_id" or "a/nested/collection/document_id"
+/// * 'options' A field mask, a transaction or a read time
+pub fn batch_read<T>(
+    auth: &impl FirebaseAuthBearer,
+    paths: &[impl AsRef<str>],
+    options: ReadOptions,
+) -> Result<BatchRead<T>>
 where
     for<'b> T: Deserialize<'b>,
 {
-    let document_name = format!(
-        "projects/{}/databases/(default)/documents/{}/{}",
-        auth.project_id(),
-        path,
-        document_id.as_ref()
-    );
-    read_by_name(auth, &document_name)
+    let url = firebase_url_action(auth.project_id(), "batchGet");
+    let request = batch_get_request(auth.project_id(), paths, &options);
+
+    let resp = exp_backoff(
+        || {
+            let resp = auth
+                .client()
+                .post(&url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(&request)
+                .send()
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error(resp, || "batchGet".to_owned()) {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    // An aborted read within a transaction is not retried. The entire transaction is retried instead.
+                    if retryable_http_status(status) && status != 409 {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )?;
+
+    let json: Option<Vec<dto::BatchGetDocumentsResponse>> = resp.json()?;
+    batch_read_result(json.unwrap_or_default())
 }
 
 ///
-/// [Async] Read a document of a specific type from a collection
+/// [Async] Read multiple documents of a specific type with a single request.
+///
+/// The order of the results does not necessarily match the order of the given paths.
+/// Documents that do not exist are reported in [`BatchRead::missing`].
 ///
 /// ## Arguments
 /// * 'auth' The authentication token
-/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
-/// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
-pub async fn read_async<T>(auth: &impl FirebaseAuthBearer, path: &str, document_id: impl AsRef<str>) -> Result<T>
+/// * 'paths' The document paths; For example "my_collection/document_id" or "a/nested/collection/document_id"
+/// * 'options' A field mask, a transaction or a read time
+pub async fn batch_read_async<T>(
+    auth: &impl FirebaseAuthBearer,
+    paths: &[impl AsRef<str>],
+    options: ReadOptions,
+) -> Result<BatchRead<T>>
 where
     for<'b> T: Deserialize<'b>,
 {
-    let document_name = format!(
-        "projects/{}/databases/(default)/documents/{}/{}",
-        auth.project_id(),
-        path,
-        document_id.as_ref()
+    let url = firebase_url_action(auth.project_id(), "batchGet");
+    let request = batch_get_request(auth.project_id(), paths, &options);
+
+    let resp = exp_backoff_async(
+        || async {
+            let resp = auth
+                .client_async()
+                .post(&url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(&request)
+                .send()
+                .await
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_async(resp, || "batchGet".to_owned()).await {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    // An aborted read within a transaction is not retried. The entire transaction is retried instead.
+                    if retryable_http_status(status) && status != 409 {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+    .await?;
+
+    let json: Option<Vec<dto::BatchGetDocumentsResponse>> = resp.json().await?;
+    batch_read_result(json.unwrap_or_default())
+}
+
+fn batch_get_request(
+    project_id: &str,
+    paths: &[impl AsRef<str>],
+    options: &ReadOptions,
+) -> dto::BatchGetDocumentsRequest {
+    dto::BatchGetDocumentsRequest {
+        documents: Some(
+            paths
+                .iter()
+                .map(|path| document_name(project_id, path.as_ref()))
+                .collect(),
+        ),
+        mask: options.document_mask(),
+        transaction: options.transaction.clone(),
+        read_time: options.read_time_string(),
+        ..Default::default()
+    }
+}
+
+fn batch_read_result<T>(responses: Vec<dto::BatchGetDocumentsResponse>) -> Result<BatchRead<T>>
+where
+    for<'b> T: Deserialize<'b>,
+{
+    let mut result = BatchRead {
+        found: Vec::new(),
+        missing: Vec::new(),
+    };
+    for response in responses {
+        if let Some(doc) = response.found {
+            result.found.push(DocumentSnapshot::from_document(&doc)?);
+        } else if let Some(name) = response.missing {
+            result.missing.push(name);
+        }
+    }
+    Ok(result)
+}
+
+#[test]
+fn batch_get_request_test() {
+    let options = ReadOptions {
+        mask: Some(vec!["a_string".to_owned()]),
+        read_time: Some(
+            chrono::DateTime::parse_from_rfc3339("2020-01-01T10:00:00Z")
+                .unwrap()
+                .into(),
+        ),
+        ..Default::default()
+    };
+    let request = batch_get_request("p", &["tests/a", "tests/b"], &options);
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        serde_json::json!({
+            "documents": [
+                "projects/p/databases/(default)/documents/tests/a",
+                "projects/p/databases/(default)/documents/tests/b"
+            ],
+            "mask": { "fieldPaths": ["a_string"] },
+            "readTime": "2020-01-01T10:00:00.000000000Z"
+        })
     );
-    read_by_name_async(auth, &document_name).await
 }
@@ -0,0 +1,112 @@
+use super::*;
+use chrono::{DateTime, Utc};
+
+///
+/// A document together with its parsed metadata.
+///
+/// Returned by [`read_snapshot`], [`List::snapshots`], [`list_snapshots_async`], [`QueryBuilder::run_typed`],
+/// [`QueryBuilder::paginate_typed`], [`batch_read`] and [`listen`].
+/// A snapshot of a document that does not exist has no data and no create or update time, see [`DocumentSnapshot::exists`].
+#[derive(Clone, Debug)]
+pub struct DocumentSnapshot<T> {
+    /// The absolute document name, for example "projects/my_project/databases/(default)/documents/my_collection/document_id"
+    pub name: String,
+    /// The time the document has been created
+    pub create_time: Option<DateTime<Utc>>,
+    /// The time the document has last been changed
+    pub update_time: Option<DateTime<Utc>>,
+    /// The document data. This is None if the document does not exist.
+    pub data: Option<T>,
+}
+
+impl<T> DocumentSnapshot<T> {
+    /// Creates a snapshot for the document with the given absolute name, that does not exist
+    pub fn missing(name: impl Into<String>) -> Self {
+        DocumentSnapshot {
+            name: name.into(),
+            create_time: None,
+            update_time: None,
+            data: None,
+        }
+    }
+
+    /// Deserializes the given document and parses its metadata
+    pub fn from_document(document: &dto::Document) -> Result<Self>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        Ok(DocumentSnapshot {
+            name: document.name.clone(),
+            create_time: parse_time(
+                &document.create_time,
+                "Failed to parse rfc3339 date from 'create_time' field",
+            )?,
+            update_time: parse_time(
+                &document.update_time,
+                "Failed to parse rfc3339 date from 'update_time' field",
+            )?,
+            data: Some(document_to_pod(document)?),
+        })
+    }
+
+    /// Returns true if the document exists
+    pub fn exists(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// The document id, for example "document_id"
+    pub fn id(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// The relative document path, for example "my_collection/document_id".
+    /// This is usually used for [`write`] or [`delete`].
+    pub fn path(&self) -> &str {
+        abs_to_rel(&self.name)
+    }
+
+    /// The relative path of the collection that contains the document, for example "my_collection"
+    pub fn parent(&self) -> &str {
+        let path = self.path();
+        match path.rfind('/') {
+            Some(index) => &path[..index],
+            None => "",
+        }
+    }
+
+    /// Returns the document data, or None if the document does not exist
+    pub fn into_data(self) -> Option<T> {
+        self.data
+    }
+}
+
+pub(super) fn parse_time(value: &Option<String>, error: &'static str) -> Result<Option<DateTime<Utc>>> {
+    match value {
+        Some(value) => Ok(Some(
+            DateTime::parse_from_rfc3339(value)
+                .map_err(|_| FirebaseError::Generic(error))?
+                .with_timezone(&Utc),
+        )),
+        None => Ok(None),
+    }
+}
+
+#[test]
+fn document_snapshot_test() {
+    let mut document = pod_to_document(&serde_json::json!({ "a": 1 })).unwrap();
+    document.name = "projects/p/databases/(default)/documents/users/u1/orders/o1".to_owned();
+    document.create_time = Some("2020-01-22T10:00:00.123456Z".to_owned());
+
+    let snapshot = DocumentSnapshot::<serde_json::Value>::from_document(&document).unwrap();
+    assert!(snapshot.exists());
+    assert_eq!(snapshot.id(), "o1");
+    assert_eq!(snapshot.path(), "users/u1/orders/o1");
+    assert_eq!(snapshot.parent(), "users/u1/orders");
+    assert_eq!(snapshot.create_time.unwrap().timestamp_subsec_micros(), 123456);
+    assert_eq!(snapshot.update_time, None);
+    assert_eq!(snapshot.data.unwrap()["a"], 1);
+
+    let missing = DocumentSnapshot::<serde_json::Value>::missing("projects/p/databases/(default)/documents/users/u2");
+    assert!(!missing.exists());
+    assert_eq!(missing.parent(), "users");
+}
@@ -0,0 +1,434 @@
+use super::*;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// The maximum number of attempts for [`run_transaction`] and [`run_transaction_async`].
+/// A transaction that got aborted by Firestore because of a concurrent modification is retried
+/// until this number of attempts is reached.
+pub const TRANSACTION_MAX_ATTEMPTS: usize = 5;
+
+/// A read-write transaction. This type is handed to the closure of [`run_transaction`]
+/// and [`run_transaction_async`].
+///
+/// Reads are performed within the transaction, writes are buffered and only
+/// committed atomically after the closure returned successfully.
+pub struct Transaction<'a, BEARER> {
+    auth: &'a BEARER,
+    id: String,
+    writes: Arc<Mutex<Vec<dto::Write>>>,
+}
+
+impl<'a, BEARER> Clone for Transaction<'a, BEARER> {
+    fn clone(&self) -> Self {
+        Transaction {
+            auth: self.auth,
+            id: self.id.clone(),
+            writes: self.writes.clone(),
+        }
+    }
+}
+
+impl<'a, BEARER> Transaction<'a, BEARER>
+where
+    BEARER: FirebaseAuthBearer,
+{
+    fn new(auth: &'a BEARER, id: String) -> Self {
+        Transaction {
+            auth,
+            id,
+            writes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// The transaction id as returned by Firestore
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    ///
+    /// Read a document of a specific type from a collection within this transaction
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+    pub fn read<T>(&self, path: &str, document_id: impl AsRef<str>) -> Result<T>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        let name = document_name(self.auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+        self.read_by_name(&name)
+    }
+
+    ///
+    /// Read a document of a specific type by its Firestore document name within this transaction
+    ///
+    /// ## Arguments
+    /// * 'document_name' The document path / collection and document id; For example "projects/my_project/databases/(default)/documents/tests/test"
+    pub fn read_by_name<T>(&self, document_name: impl AsRef<str>) -> Result<T>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        let params = [("transaction", self.id.clone())];
+        let json = get_document_with(self.auth, document_name.as_ref(), &params, aborted_error)?;
+        Ok(document_to_pod(&json)?)
+    }
+
+    ///
+    /// [Async] Read a document of a specific type from a collection within this transaction
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id to the path argument.
+    pub async fn read_async<T>(&self, path: &str, document_id: impl AsRef<str>) -> Result<T>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        let name = document_name(self.auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+        self.read_by_name_async(&name).await
+    }
+
+    ///
+    /// [Async] Read a document of a specific type by its Firestore document name within this transaction
+    ///
+    /// ## Arguments
+    /// * 'document_name' The document path / collection and document id; For example "projects/my_project/databases/(default)/documents/tests/test"
+    pub async fn read_by_name_async<T>(&self, document_name: impl AsRef<str>) -> Result<T>
+    where
+        for<'b> T: Deserialize<'b>,
+    {
+        let params = [("transaction", self.id.clone())];
+        let json = get_document_with_async(self.auth, document_name.as_ref(), &params, aborted_error).await?;
+        Ok(document_to_pod(&json)?)
+    }
+
+    ///
+    /// Buffer a write of the given document. The write is performed when the transaction is committed.
+    ///
+    /// ## Arguments
+    /// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+    /// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+    /// * 'document' The document
+    /// * 'options' Write options
+    pub fn write<T>(&self, path: &str, document_id: impl AsRef<str>, document: &T, options: WriteOptions) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let write = transforms_update(self.auth, path, document_id.as_ref(), document, &options)?;
+        self.writes.lock().unwrap().push(write);
+        Ok(())
+    }
+
+    ///
+    /// Buffer the deletion of the document at the given path. The document is deleted when the transaction is committed.
+    ///
+    /// ## Arguments
+    /// * 'path' The relative collection path and document id, for example "my_collection/document_id"
+    pub fn delete(&self, path: &str) {
+        let write = dto::Write {
+            delete: Some(document_name(self.auth.project_id(), path)),
+            ..Default::default()
+        };
+        self.writes.lock().unwrap().push(write);
+    }
+
+    fn take_writes(&self) -> Vec<dto::Write> {
+        std::mem::take(&mut *self.writes.lock().unwrap())
+    }
+}
+
+/// Maps the errors of the requests of a transaction. Firestore aborts a transaction with "409 ABORTED"
+/// if there has been a concurrent modification. Such errors are reported as [`FirebaseError::Aborted`].
+fn aborted_error(status: &str, err: FirebaseError) -> FirebaseError {
+    match (status, err) {
+        ("ABORTED", FirebaseError::APIError(_, message, context)) => FirebaseError::Aborted(message, context),
+        (_, err) => err,
+    }
+}
+
+/// Only aborted transactions are retried.
+/// Other conflicts, like "409 ALREADY_EXISTS" of a document creation, are not retried.
+#[inline]
+fn is_aborted(err: &FirebaseError) -> bool {
+    match err {
+        FirebaseError::Aborted(_, _) => true,
+        _ => false,
+    }
+}
+
+///
+/// Runs the given closure within a read-write transaction.
+///
+/// All reads in the closure should be performed via the given [`Transaction`].
+/// Writes are buffered and committed atomically after the closure returned successfully.
+/// If the closure returns an error, the transaction is rolled back and the error is returned.
+///
+/// If Firestore aborts the transaction because of a concurrent modification,
+/// the entire closure is executed again, up to [`TRANSACTION_MAX_ATTEMPTS`] times.
+/// The closure should therefore not have side effects apart from the transaction writes.
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Counter { value: i64 }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let new_value = documents::run_transaction(&session, |tx| {
+///     let counter: Counter = tx.read("tests", "counter")?;
+///     let counter = Counter { value: counter.value + 1 };
+///     tx.write("tests", "counter", &counter, documents::WriteOptions::default())?;
+///     Ok(counter.value)
+/// })?;
+/// println!("Counter is now {}", new_value);
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'f' The closure that performs reads and writes via the given [`Transaction`]
+pub fn run_transaction<'a, BEARER, F, R>(auth: &'a BEARER, mut f: F) -> Result<R>
+where
+    BEARER: FirebaseAuthBearer,
+    F: FnMut(&Transaction<'a, BEARER>) -> Result<R>,
+{
+    let mut retry_transaction = None;
+    let mut attempt = 1;
+
+    loop {
+        let transaction = Transaction::new(auth, begin_transaction(auth, retry_transaction.take())?);
+
+        let err = match f(&transaction) {
+            Ok(value) => match commit_with(
+                auth,
+                transaction.take_writes(),
+                Some(transaction.id.clone()),
+                aborted_error,
+            ) {
+                Ok(_) => return Ok(value),
+                Err(err) => err,
+            },
+            Err(err) => {
+                // The closure error is more relevant than a failed rollback
+                let _ = rollback(auth, &transaction.id);
+                err
+            }
+        };
+
+        if !is_aborted(&err) || attempt >= TRANSACTION_MAX_ATTEMPTS {
+            return Err(err);
+        }
+        attempt += 1;
+        retry_transaction = Some(transaction.id);
+    }
+}
+
+///
+/// [Async] Runs the given closure within a read-write transaction.
+///
+/// The closure gets the [`Transaction`] by value and returns a future.
+/// See [`run_transaction`] for the retry and rollback behaviour.
+///
+/// Example:
+/// ```rust
+/// # use serde::{Serialize, Deserialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Counter { value: i64 }
+///
+/// use firestore_db_and_auth::documents;
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+/// # let mut rt = tokio::runtime::Runtime::new()?;
+///
+/// let new_value = rt.block_on(documents::run_transaction_async(&session, |tx| async move {
+///     let counter: Counter = tx.read_async("tests", "counter").await?;
+///     let counter = Counter { value: counter.value + 1 };
+///     tx.write("tests", "counter", &counter, documents::WriteOptions::default())?;
+///     Ok(counter.value)
+/// }))?;
+/// println!("Counter is now {}", new_value);
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'f' The closure that performs reads and writes via the given [`Transaction`]
+pub async fn run_transaction_async<'a, BEARER, F, Fut, R>(auth: &'a BEARER, mut f: F) -> Result<R>
+where
+    BEARER: FirebaseAuthBearer,
+    F: FnMut(Transaction<'a, BEARER>) -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let mut retry_transaction = None;
+    let mut attempt = 1;
+
+    loop {
+        let transaction = Transaction::new(auth, begin_transaction_async(auth, retry_transaction.take()).await?);
+
+        let err = match f(transaction.clone()).await {
+            Ok(value) => match commit_with_async(
+                auth,
+                transaction.take_writes(),
+                Some(transaction.id.clone()),
+                aborted_error,
+            )
+            .await
+            {
+                Ok(_) => return Ok(value),
+                Err(err) => err,
+            },
+            Err(err) => {
+                // The closure error is more relevant than a failed rollback
+                let _ = rollback_async(auth, &transaction.id).await;
+                err
+            }
+        };
+
+        if !is_aborted(&err) || attempt >= TRANSACTION_MAX_ATTEMPTS {
+            return Err(err);
+        }
+        attempt += 1;
+        retry_transaction = Some(transaction.id);
+    }
+}
+
+#[inline]
+fn begin_transaction_request(retry_transaction: Option<String>) -> dto::BeginTransactionRequest {
+    dto::BeginTransactionRequest {
+        options: Some(dto::TransactionOptions {
+            read_write: Some(dto::ReadWrite { retry_transaction }),
+            ..Default::default()
+        }),
+    }
+}
+
+fn begin_transaction(auth: &impl FirebaseAuthBearer, retry_transaction: Option<String>) -> Result<String> {
+    let url = firebase_url_action(auth.project_id(), "beginTransaction");
+    let request = begin_transaction_request(retry_transaction);
+
+    let resp = exp_backoff(
+        || {
+            let resp = auth
+                .client()
+                .post(&url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(&request)
+                .send()
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_with(resp, || auth.project_id().to_owned(), aborted_error) {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )?;
+
+    let json: dto::BeginTransactionResponse = resp.json()?;
+    json.transaction
+        .ok_or_else(|| FirebaseError::Generic("No transaction id in the beginTransaction response"))
+}
+
+async fn begin_transaction_async(auth: &impl FirebaseAuthBearer, retry_transaction: Option<String>) -> Result<String> {
+    let url = firebase_url_action(auth.project_id(), "beginTransaction");
+    let request = begin_transaction_request(retry_transaction);
+
+    let resp = exp_backoff_async(
+        || async {
+            let resp = auth
+                .client_async()
+                .post(&url)
+                .bearer_auth(auth.access_token().to_owned())
+                .json(&request)
+                .send()
+                .await
+                .map_err(|err| backoff::Error::Permanent(FirebaseError::from(err)))?;
+
+            let status = resp.status().as_u16();
+
+            match extract_google_api_error_with_async(resp, || auth.project_id().to_owned(), aborted_error).await {
+                Ok(new_resp) => Ok(new_resp),
+                Err(err) => {
+                    if retryable_http_status(status) {
+                        Err(backoff::Error::Transient(err))
+                    } else {
+                        Err(backoff::Error::Permanent(err))
+                    }
+                }
+            }
+        },
+        FIRESTORE_REQUEST_RETRY_MAX_ELAPSED_TIME,
+    )
+    .await?;
+
+    let json: dto::BeginTransactionResponse = resp.json().await?;
+    json.transaction
+        .ok_or_else(|| FirebaseError::Generic("No transaction id in the beginTransaction response"))
+}
+
+fn rollback(auth: &impl FirebaseAuthBearer, transaction: &str) -> Result<()> {
+    let url = firebase_url_action(auth.project_id(), "rollback");
+    let request = dto::RollbackRequest {
+        transaction: Some(transaction.to_owned()),
+    };
+
+    let resp = auth
+        .client()
+        .post(&url)
+        .bearer_auth(auth.access_token().to_owned())
+        .json(&request)
+        .send()?;
+
+    extract_google_api_error(resp, || transaction.to_owned())?;
+
+    Ok(())
+}
+
+async fn rollback_async(auth: &impl FirebaseAuthBearer, transaction: &str) -> Result<()> {
+    let url = firebase_url_action(auth.project_id(), "rollback");
+    let request = dto::RollbackRequest {
+        transaction: Some(transaction.to_owned()),
+    };
+
+    let resp = auth
+        .client_async()
+        .post(&url)
+        .bearer_auth(auth.access_token().to_owned())
+        .json(&request)
+        .send()
+        .await?;
+
+    extract_google_api_error_async(resp, || transaction.to_owned()).await?;
+
+    Ok(())
+}
+
+#[test]
+fn aborted_error_test() {
+    let err = aborted_error(
+        "ABORTED",
+        FirebaseError::APIError(409, "m".to_owned(), "ctx".to_owned()),
+    );
+    assert!(is_aborted(&err));
+    let err = aborted_error(
+        "ALREADY_EXISTS",
+        FirebaseError::APIError(409, "m".to_owned(), "ctx".to_owned()),
+    );
+    assert!(!is_aborted(&err));
+}
@@ -0,0 +1,273 @@
+use super::*;
+
+/// A number for [`FieldValue::Increment`], [`FieldValue::Maximum`] and [`FieldValue::Minimum`].
+/// Create it from any integer or floating point number, for example `FieldValue::Increment(1.into())`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumericValue {
+    /// A 64 bit signed integer
+    Integer(i64),
+    /// A 64 bit floating point number
+    Double(f64),
+}
+
+macro_rules! numeric_value_from {
+    ($variant:ident: $($t:ty),*) => {
+        $(
+            impl From<$t> for NumericValue {
+                fn from(v: $t) -> Self {
+                    NumericValue::$variant(v.into())
+                }
+            }
+        )*
+    };
+}
+
+numeric_value_from!(Integer: i8, i16, i32, i64, u8, u16, u32);
+numeric_value_from!(Double: f32, f64);
+
+impl From<NumericValue> for dto::Value {
+    fn from(value: NumericValue) -> Self {
+        match value {
+            NumericValue::Integer(v) => dto::Value {
+                integer_value: Some(v.to_string()),
+                ..Default::default()
+            },
+            NumericValue::Double(v) => dto::Value {
+                double_value: Some(v),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// A value that is computed by the Firestore server when a write is applied.
+///
+/// Use those with [`transform`], [`WriteOptions::transforms`] or [`WriteBatch::transform`].
+/// The resulting field values are returned as [`WriteResult::transform_results`].
+/// [`FieldValue::Delete`] does not produce a transform result.
+#[derive(Clone, Debug)]
+pub enum FieldValue {
+    /// Sets the field to the time at which the server processed the request
+    ServerTimestamp,
+    /// Adds the given integer or floating point number to the current field value.
+    /// A missing or non-numeric field is set to the given value.
+    Increment(NumericValue),
+    /// Sets the field to the maximum of its current value and the given value
+    Maximum(NumericValue),
+    /// Sets the field to the minimum of its current value and the given value
+    Minimum(NumericValue),
+    /// Appends the given elements to an array field, if they are not already present
+    ArrayUnion(Vec<serde_json::Value>),
+    /// Removes all instances of the given elements from an array field
+    ArrayRemove(Vec<serde_json::Value>),
+    /// Removes the field from the document.
+    /// With [`WriteOptions::transforms`] this requires [`WriteOptions::merge`] or [`WriteOptions::merge_fields`].
+    Delete,
+}
+
+impl FieldValue {
+    /// The field transform of this value. A [`FieldValue::Delete`] is not a transform, but part of the update mask.
+    /// Fails if a value can not be converted.
+    fn to_field_transform(&self, field_path: &str) -> Result<Option<dto::FieldTransform>> {
+        let mut transform = dto::FieldTransform {
+            field_path: Some(field_path.to_owned()),
+            ..Default::default()
+        };
+        match self {
+            FieldValue::ServerTimestamp => transform.set_to_server_value = Some("REQUEST_TIME".to_owned()),
+            FieldValue::Increment(v) => transform.increment = Some((*v).into()),
+            FieldValue::Maximum(v) => transform.maximum = Some((*v).into()),
+            FieldValue::Minimum(v) => transform.minimum = Some((*v).into()),
+            FieldValue::ArrayUnion(values) => transform.append_missing_elements = Some(array_value(values)?),
+            FieldValue::ArrayRemove(values) => transform.remove_all_from_array = Some(array_value(values)?),
+            FieldValue::Delete => return Ok(None),
+        };
+        Ok(Some(transform))
+    }
+}
+
+#[inline]
+fn array_value(values: &[serde_json::Value]) -> Result<dto::ArrayValue> {
+    Ok(dto::ArrayValue {
+        values: Some(values.iter().map(value_to_firebase).collect::<Result<_>>()?),
+    })
+}
+
+/// Splits the given transforms into field transforms and the paths of the fields to delete
+fn split_transforms<'b>(
+    transforms: impl IntoIterator<Item = (&'b str, &'b FieldValue)>,
+) -> Result<(Vec<dto::FieldTransform>, Vec<String>)> {
+    let mut field_transforms = Vec::new();
+    let mut deletes = Vec::new();
+    for (field_path, value) in transforms {
+        match value.to_field_transform(field_path)? {
+            Some(transform) => field_transforms.push(transform),
+            None => deletes.push(field_path.to_owned()),
+        }
+    }
+    Ok((field_transforms, deletes))
+}
+
+/// Adds the given field transforms to a document update. The transforms are applied after the update.
+///
+/// Fields to delete are added to the update mask. This fails if the update has no mask, i.e. is not a merge.
+pub(super) fn transform_write<'b>(
+    mut write: dto::Write,
+    transforms: impl IntoIterator<Item = (&'b str, &'b FieldValue)>,
+) -> Result<dto::Write> {
+    let (field_transforms, deletes) = split_transforms(transforms)?;
+    if !deletes.is_empty() {
+        let mask = write
+            .update_mask
+            .as_mut()
+            .ok_or_else(|| FirebaseError::Generic("FieldValue::Delete requires a merge"))?;
+        mask.field_paths.extend(deletes);
+    }
+    if !field_transforms.is_empty() {
+        write.update_transforms = Some(field_transforms);
+    }
+    Ok(write)
+}
+
+/// Creates a write that only applies the given field transforms to the document with the given absolute name.
+/// No other field is written. A not yet existing document will be created.
+pub(super) fn field_transforms_write(name: String, transforms: &[(String, FieldValue)]) -> Result<dto::Write> {
+    let (field_transforms, deletes) = split_transforms(
+        transforms
+            .iter()
+            .map(|(field_path, value)| (field_path.as_str(), value)),
+    )?;
+    Ok(dto::Write {
+        update: Some(dto::Document {
+            name,
+            fields: Some(Default::default()),
+            ..Default::default()
+        }),
+        update_mask: Some(dto::DocumentMask { field_paths: deletes }),
+        update_transforms: if field_transforms.is_empty() {
+            None
+        } else {
+            Some(field_transforms)
+        },
+        ..Default::default()
+    })
+}
+
+/// Converts the result of a single write commit into a [`WriteResult`]
+pub(super) fn commit_to_write_result(document_id: &str, response: dto::CommitResponse) -> Result<WriteResult> {
+    let result = response
+        .write_results
+        .and_then(|results| results.into_iter().next())
+        .ok_or_else(|| FirebaseError::Generic("No write result in the commit response"))?;
+
+    let update_time = match result.update_time {
+        Some(f) => Some(
+            chrono::DateTime::parse_from_rfc3339(&f)
+                .map_err(|_| FirebaseError::Generic("Failed to parse rfc3339 date from 'update_time' field"))?
+                .with_timezone(&chrono::Utc),
+        ),
+        None => None,
+    };
+
+    Ok(WriteResult {
+        document_id: document_id.to_owned(),
+        // A commit does not report the creation time
+        create_time: None,
+        update_time,
+        transform_results: result.transform_results.unwrap_or_default(),
+    })
+}
+
+///
+/// Applies the given server side field transforms to a document, without writing any other field.
+/// A not yet existing document will be created.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::documents::{self, FieldValue};
+/// # use firestore_db_and_auth::{credentials::Credentials, ServiceSession, errors::Result};
+///
+/// # let credentials = Credentials::new(include_str!("../../firebase-service-account.json"),
+///                                         &[include_str!("../../tests/service-account-for-tests.jwks")])?;
+/// # let session = ServiceSession::new(credentials)?;
+///
+/// let result = documents::transform(&session, "tests", "page_views", &[
+///     ("views".to_owned(), FieldValue::Increment(1.into())),
+///     ("last_view".to_owned(), FieldValue::ServerTimestamp),
+/// ])?;
+/// println!("views: {:?}", result.transform_results[0]);
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+/// * 'transforms' Pairs of field path and server value, like [`WriteOptions::transforms`]
+pub fn transform(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+    transforms: &[(String, FieldValue)],
+) -> Result<WriteResult> {
+    let name = document_name(auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+    let response = commit(auth, vec![field_transforms_write(name, transforms)?], None)?;
+    commit_to_write_result(document_id.as_ref(), response)
+}
+
+///
+/// [Async] Applies the given server side field transforms to a document, without writing any other field.
+/// A not yet existing document will be created.
+///
+/// ## Arguments
+/// * 'auth' The authentication token
+/// * 'path' The document path / collection; For example "my_collection" or "a/nested/collection"
+/// * 'document_id' The document id. Make sure that you do not include the document id in the path argument.
+/// * 'transforms' Pairs of field path and server value, like [`WriteOptions::transforms`]
+pub async fn transform_async(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: impl AsRef<str>,
+    transforms: &[(String, FieldValue)],
+) -> Result<WriteResult> {
+    let name = document_name(auth.project_id(), &format!("{}/{}", path, document_id.as_ref()));
+    let response = commit_async(auth, vec![field_transforms_write(name, transforms)?], None).await?;
+    commit_to_write_result(document_id.as_ref(), response)
+}
+
+#[test]
+fn field_transforms_write_test() {
+    let write = field_transforms_write(
+        "projects/p/databases/(default)/documents/tests/a".to_owned(),
+        &[
+            ("views".to_owned(), FieldValue::Increment(1.into())),
+            ("updated".to_owned(), FieldValue::ServerTimestamp),
+            ("highscore".to_owned(), FieldValue::Maximum(2.5.into())),
+            ("obsolete".to_owned(), FieldValue::Delete),
+        ],
+    )
+    .unwrap();
+    let json = serde_json::to_value(&write).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "update": {
+                "name": "projects/p/databases/(default)/documents/tests/a",
+                "fields": {}
+            },
+            "updateMask": { "fieldPaths": ["obsolete"] },
+            "updateTransforms": [
+                { "fieldPath": "views", "increment": { "integerValue": "1" } },
+                { "fieldPath": "updated", "setToServerValue": "REQUEST_TIME" },
+                { "fieldPath": "highscore", "maximum": { "doubleValue": 2.5 } }
+            ]
+        })
+    );
+
+    let too_large = serde_json::Value::from(u64::max_value());
+    let write = field_transforms_write(
+        "projects/p/databases/(default)/documents/tests/a".to_owned(),
+        &[("tags".to_owned(), FieldValue::ArrayUnion(vec![too_large]))],
+    );
+    assert!(write.is_err());
+}
@@ -5,19 +5,170 @@ use super::*;
 /// This structure contains the document id of the written document.
 #[derive(Serialize, Deserialize)]
 pub struct WriteResult {
-    ///
+    /// The time the document has been created. This is None for writes with field transforms
+    /// (see [`WriteOptions::transforms`] and [`transform`]), because a commit does not report it.
     pub create_time: Option<chrono::DateTime<chrono::Utc>>,
     pub update_time: Option<chrono::DateTime<chrono::Utc>>,
     pub document_id: String,
+    /// The values of the fields after the field transforms of [`WriteOptions::transforms`]
+    /// have been applied, in the same order as the transforms.
+    #[serde(default)]
+    pub transform_results: Vec<dto::Value>,
+}
+
+/// A condition that must hold for a write or delete to be applied.
+///
+/// If the condition does not hold, a [`FirebaseError::FailedPrecondition`] is returned.
+#[derive(Clone, Debug)]
+pub enum Precondition {
+    /// The target document must exist
+    MustExist,
+    /// The target document must not exist
+    MustNotExist,
+    /// The target document must exist and must have been last updated at the given time.
+    /// Use the update time of a [`WriteResult`] for compare-and-swap updates.
+    UpdateTime(chrono::DateTime<chrono::Utc>),
+}
+
+impl Precondition {
+    pub(super) fn to_dto(&self) -> dto::Precondition {
+        match self {
+            Precondition::MustExist => dto::Precondition {
+                exists: Some(true),
+                ..Default::default()
+            },
+            Precondition::MustNotExist => dto::Precondition {
+                exists: Some(false),
+                ..Default::default()
+            },
+            Precondition::UpdateTime(update_time) => dto::Precondition {
+                update_time: Some(update_time.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The precondition as url query parameter
+    pub(super) fn to_query_param(&self) -> (&'static str, String) {
+        match self {
+            Precondition::MustExist => ("currentDocument.exists", "true".to_owned()),
+            Precondition::MustNotExist => ("currentDocument.exists", "false".to_owned()),
+            Precondition::UpdateTime(update_time) => (
+                "currentDocument.updateTime",
+                update_time.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+            ),
+        }
+    }
+}
+
+/// Maps the errors of a write or delete with an explicit precondition. Besides FAILED_PRECONDITION, Firestore reports
+/// a missing document (NOT_FOUND) or an already existing document (ALREADY_EXISTS) if the precondition does not hold.
+pub(super) fn precondition_error(status: &str, err: FirebaseError) -> FirebaseError {
+    match (status, err) {
+        ("FAILED_PRECONDITION", FirebaseError::APIError(_, message, context))
+        | ("NOT_FOUND", FirebaseError::APIError(_, message, context))
+        | ("ALREADY_EXISTS", FirebaseError::APIError(_, message, context)) => {
+            FirebaseError::FailedPrecondition(message, context)
+        }
+        (_, err) => err,
+    }
 }
 
 /// Write options. The default will overwrite a target document and not merge fields.
 #[derive(Default)]
 pub struct WriteOptions {
     /// If this is set instead of overwriting all fields of a target document, only the given fields will be merged.
+    /// Keys of map fields are merged individually, other keys of a map field are kept.
     /// This only works if your document type has Option fields.
     /// The write will fail, if no document_id is given or the target document does not exist yet.
     pub merge: bool,
+    /// Like [`WriteOptions::merge`], but only the given field paths are merged, for example "a_map.some_key".
+    /// A field in this list that is not set in the document will be deleted.
+    /// The paths are used as given, see [`field_path`] for field names that need quoting.
+    pub merge_fields: Vec<String>,
+    /// Server side field transforms like [`FieldValue::ServerTimestamp`] or [`FieldValue::Increment`],
+    /// given as pairs of field path and value. The transforms are applied after the document has been written.
+    /// Writing with transforms requires a document_id. Use [`FieldValue::Delete`] to remove a field during a merge.
+    pub transforms: Vec<(String, FieldValue)>,
+    /// A condition that must hold for the write to be applied. Writing with a precondition requires a document_id.
+    /// This replaces the implicit "document must exist" condition of [`WriteOptions::merge`].
+    pub precondition: Option<Precondition>,
+    /// Options of the document serialization, for example whether strings that look like dates are stored as
+    /// timestamps. See [`SerializeOptions`].
+    pub serialize_options: SerializeOptions,
+}
+
+impl WriteOptions {
+    /// Only write if the target document exists
+    pub fn must_exist(mut self) -> Self {
+        self.precondition = Some(Precondition::MustExist);
+        self
+    }
+
+    /// Only write if the target document does not exist yet
+    pub fn must_not_exist(mut self) -> Self {
+        self.precondition = Some(Precondition::MustNotExist);
+        self
+    }
+
+    /// Only write if the target document has been last updated at the given time
+    pub fn if_update_time(mut self, update_time: chrono::DateTime<chrono::Utc>) -> Self {
+        self.precondition = Some(Precondition::UpdateTime(update_time));
+        self
+    }
+}
+
+/// Creates a document update including the field transforms and the precondition of the given options
+pub(super) fn transforms_update<T>(
+    auth: &impl FirebaseAuthBearer,
+    path: &str,
+    document_id: &str,
+    document: &T,
+    options: &WriteOptions,
+) -> Result<dto::Write>
+where
+    T: Serialize,
+{
+    let name = document_name(auth.project_id(), &format!("{}/{}", path, document_id));
+    let merge = options.merge || !options.merge_fields.is_empty();
+    let mut write = update_write(name, document, merge, options.serialize_options)?;
+    if !options.merge_fields.is_empty() {
+        write.update_mask = Some(dto::DocumentMask {
+            field_paths: options.merge_fields.clone(),
+        });
+    }
+    if let Some(precondition) = &options.precondition {
+        write.current_document = Some(precondition.to_dto());
+    }
+    transform_write(
+        write,
+        options
+            .transforms
+            .iter()
+            .map(|(field_path, value)| (field_path.as_str(), value)),
+    )
+}
+
+/// The url query parameters of a write: The precondition and the update mask of a merge
+fn write_params(options: &WriteOptions, document: &dto::Document) -> Vec<(&'static str, String)> {
+    let field_paths = if !options.merge_fields.is_empty() {
+        Some(options.merge_fields.clone())
+    } else if options.merge {
+        document.fields.as_ref().map(document_field_paths)
+    } else {
+        None
+    };
+
+    let mut params = Vec::new();
+    match &options.precondition {
+        Some(precondition) => params.push(precondition.to_query_param()),
+        None if field_paths.is_some() => params.push(("currentDocument.exists", "true".to_owned())),
+        None => {}
+    }
+    for field_path in field_paths.unwrap_or_default() {
+        params.push(("updateMask.fieldPaths", field_path));
+    }
+    params
 }
 
 ///
@@ -28,6 +179,12 @@ pub struct WriteOptions {
 /// If a document_id is given, the document will be created if it does not yet exist.
 /// Except if the "merge" option (see [`WriteOptions::merge`]) is set.
 ///
+/// If field transforms are given (see [`WriteOptions::transforms`]), the document and the transforms
+/// are committed together. The transformed values are returned in [`WriteResult::transform_results`].
+///
+/// If a precondition is given (see [`WriteOptions::precondition`]) and does not hold,
+/// a [`FirebaseError::FailedPrecondition`] is returned.
+///
 /// Example:
 ///```rust
 ///use firestore_db_and_auth::{Credentials, ServiceSession, documents, errors::Result, FirebaseAuthBearer};
@@ -56,7 +213,7 @@ pub struct WriteOptions {
 /// /// Either via Option<> or by not having the fields in the structure, see DemoPartialDTO.
 /// fn write_partial(session: &impl FirebaseAuthBearer) -> Result<()> {
 ///    let obj = DemoPartialDTO { a_string: None, an_int: 16 };
-///    let result = documents::write(session, "tests", Some("service_test"), &obj, documents::WriteOptions{merge:true})?;
+///    let result = documents::write(session, "tests", Some("service_test"), &obj, documents::WriteOptions{merge:true, ..Default::default()})?;
 ///    println!("id: {}, created: {}, updated: {}", result.document_id, result.create_time.unwrap(), result.update_time.unwrap());
 ///    Ok(())
 /// }
@@ -88,20 +245,27 @@ pub fn write<T>(
 where
     T: Serialize,
 {
-    let mut url = match document_id.as_ref() {
+    if !options.transforms.is_empty() {
+        let document_id =
+            document_id.ok_or_else(|| FirebaseError::Generic("Field transforms require a document id"))?;
+        let write = transforms_update(auth, path, document_id.as_ref(), document, &options)?;
+        let response = commit_with(auth, vec![write], None, |status, err| match options.precondition {
+            Some(_) => precondition_error(status, err),
+            None => err,
+        })?;
+        return commit_to_write_result(document_id.as_ref(), response);
+    }
+    if options.precondition.is_some() && document_id.is_none() {
+        return Err(FirebaseError::Generic("Preconditions require a document id"));
+    }
+
+    let url = match document_id.as_ref() {
         Some(document_id) => firebase_url_extended(auth.project_id(), path, document_id.as_ref()),
         None => firebase_url(auth.project_id(), path),
     };
 
-    let firebase_document = pod_to_document(&document)?;
-
-    if options.merge && firebase_document.fields.is_some() {
-        url = format!("{}?currentDocument.exists=true", url);
-        let fields = firebase_document.fields.as_ref().unwrap().keys();
-        for f in fields {
-            url += &format!("&updateMask.fieldPaths={}", f);
-        }
-    }
+    let firebase_document = pod_to_document_with(&document, options.serialize_options)?;
+    let params = write_params(&options, &firebase_document);
 
     let builder = if document_id.is_some() {
         auth.client().patch(&url)
@@ -110,17 +274,25 @@ where
     };
 
     let resp = builder
+        .query(&params)
         .bearer_auth(auth.access_token().to_owned())
         .json(&firebase_document)
         .send()?;
 
-    let resp = extract_google_api_error(resp, || {
-        document_id
-            .as_ref()
-            .and_then(|f| Some(f.as_ref().to_owned()))
-            .or(Some(String::new()))
-            .unwrap()
-    })?;
+    let resp = extract_google_api_error_with(
+        resp,
+        || {
+            document_id
+                .as_ref()
+                .and_then(|f| Some(f.as_ref().to_owned()))
+                .or(Some(String::new()))
+                .unwrap()
+        },
+        |status, err| match options.precondition {
+            Some(_) => precondition_error(status, err),
+            None => err,
+        },
+    )?;
 
     let result_document: dto::Document = resp.json()?;
     let document_id = Path::new(&result_document.name)
@@ -151,6 +323,7 @@ where
         document_id,
         create_time,
         update_time,
+        transform_results: Vec::new(),
     })
 }
 
@@ -172,20 +345,28 @@ pub async fn write_async<T>(
 where
     T: Serialize,
 {
-    let mut url = match document_id.as_ref() {
+    if !options.transforms.is_empty() {
+        let document_id =
+            document_id.ok_or_else(|| FirebaseError::Generic("Field transforms require a document id"))?;
+        let write = transforms_update(auth, path, document_id.as_ref(), document, &options)?;
+        let response = commit_with_async(auth, vec![write], None, |status, err| match options.precondition {
+            Some(_) => precondition_error(status, err),
+            None => err,
+        })
+        .await?;
+        return commit_to_write_result(document_id.as_ref(), response);
+    }
+    if options.precondition.is_some() && document_id.is_none() {
+        return Err(FirebaseError::Generic("Preconditions require a document id"));
+    }
+
+    let url = match document_id.as_ref() {
         Some(document_id) => firebase_url_extended(auth.project_id(), path, document_id.as_ref()),
         None => firebase_url(auth.project_id(), path),
     };
 
-    let firebase_document = pod_to_document(&document)?;
-
-    if options.merge && firebase_document.fields.is_some() {
-        url = format!("{}?currentDocument.exists=true", url);
-        let fields = firebase_document.fields.as_ref().unwrap().keys();
-        for f in fields {
-            url += &format!("&updateMask.fieldPaths={}", f);
-        }
-    }
+    let firebase_document = pod_to_document_with(&document, options.serialize_options)?;
+    let params = write_params(&options, &firebase_document);
 
     let builder = if document_id.is_some() {
         auth.client_async().patch(&url)
@@ -194,18 +375,26 @@ where
     };
 
     let resp = builder
+        .query(&params)
         .bearer_auth(auth.access_token().to_owned())
         .json(&firebase_document)
         .send()
         .await?;
 
-    let resp = extract_google_api_error_async(resp, || {
-        document_id
-            .as_ref()
-            .and_then(|f| Some(f.as_ref().to_owned()))
-            .or(Some(String::new()))
-            .unwrap()
-    })
+    let resp = extract_google_api_error_with_async(
+        resp,
+        || {
+            document_id
+                .as_ref()
+                .and_then(|f| Some(f.as_ref().to_owned()))
+                .or(Some(String::new()))
+                .unwrap()
+        },
+        |status, err| match options.precondition {
+            Some(_) => precondition_error(status, err),
+            None => err,
+        },
+    )
     .await?;
 
     let result_document: dto::Document = resp.json().await?;
@@ -237,6 +426,7 @@ where
         document_id,
         create_time,
         update_time,
+        transform_results: Vec::new(),
     })
 }
 
@@ -300,6 +490,7 @@ where
         document_id,
         create_time,
         update_time,
+        transform_results: Vec::new(),
     })
 }
 
@@ -364,5 +555,6 @@ where
         document_id,
         create_time,
         update_time,
+        transform_results: Vec::new(),
     })
 }
@@ -41,6 +41,9 @@ pub struct Write {
     #[serde(rename = "updateMask")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_mask: Option<DocumentMask>,
+    #[serde(rename = "updateTransforms")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_transforms: Option<Vec<FieldTransform>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,7 +56,10 @@ pub enum FieldOperator {
     GREATER_THAN_OR_EQUAL, //	Greater than or equal. Requires that the field come first in orderBy.
     EQUAL,                 //	Equal.
     ARRAY_CONTAINS,        //	Contains. Requires that the field is an array.
-    IN,                    //   In.
+    IN,                    //   In. Requires that the value is a non-empty array.
+    NOT_EQUAL,             //	Not equal. Documents without the field or with a null value are not returned.
+    ARRAY_CONTAINS_ANY,    //	Contains any. Requires that the field is an array and the value is a non-empty array.
+    NOT_IN,                //	Not in. Requires that the value is a non-empty array.
 }
 
 impl Default for FieldOperator {
@@ -114,10 +120,15 @@ pub struct Status {
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ListenRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub labels: Option<HashMap<String, String>>,
     #[serde(rename = "addTarget")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub add_target: Option<Target>,
     #[serde(rename = "removeTarget")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub remove_target: Option<i32>,
 }
 
@@ -126,6 +137,7 @@ pub struct RunQueryRequest {
     #[serde(rename = "newTransaction")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_transaction: Option<TransactionOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction: Option<String>,
     #[serde(rename = "structuredQuery")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -144,7 +156,23 @@ pub struct FieldReference {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct UnaryFilter {
     pub field: FieldReference,
-    pub op: String,
+    pub op: UnaryOperator,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum UnaryOperator {
+    OPERATOR_UNSPECIFIED, //	Unspecified. This value must not be used.
+    IS_NAN,               //	The field is equal to NaN.
+    IS_NULL,              //	The field is equal to null.
+    IS_NOT_NAN,           //	The field is not equal to NaN. Documents without the field are not returned.
+    IS_NOT_NULL,          //	The field is not equal to null. Documents without the field are not returned.
+}
+
+impl Default for UnaryOperator {
+    fn default() -> Self {
+        UnaryOperator::OPERATOR_UNSPECIFIED
+    }
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -161,7 +189,21 @@ pub struct DocumentMask {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct CompositeFilter {
     pub filters: Vec<Filter>,
-    pub op: String,
+    pub op: CompositeOperator,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum CompositeOperator {
+    OPERATOR_UNSPECIFIED, //	Unspecified. This value must not be used.
+    AND,                  //	Documents are required to satisfy all of the combined filters.
+    OR,                   //	Documents are required to satisfy at least one of the combined filters.
+}
+
+impl Default for CompositeOperator {
+    fn default() -> Self {
+        CompositeOperator::OPERATOR_UNSPECIFIED
+    }
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -197,8 +239,10 @@ pub struct WriteResponse {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ListCollectionIdsRequest {
     #[serde(rename = "pageToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub page_token: Option<String>,
     #[serde(rename = "pageSize")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub page_size: Option<i32>,
 }
 
@@ -227,8 +271,10 @@ pub struct MapValue {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct TransactionOptions {
     #[serde(rename = "readWrite")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub read_write: Option<ReadWrite>,
     #[serde(rename = "readOnly")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub read_only: Option<ReadOnly>,
 }
 
@@ -242,14 +288,20 @@ pub struct CommitResponse {
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Target {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub documents: Option<DocumentsTarget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub once: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub query: Option<QueryTarget>,
     #[serde(rename = "resumeToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resume_token: Option<String>,
     #[serde(rename = "targetId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub target_id: Option<i32>,
     #[serde(rename = "readTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub read_time: Option<String>,
 }
 
@@ -294,6 +346,7 @@ pub struct Value {
 
     #[serde(rename = "doubleValue")]
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, with = "double_value")]
     pub double_value: Option<f64>,
 
     #[serde(rename = "mapValue")]
@@ -372,11 +425,20 @@ pub struct FieldTransform {
     #[serde(rename = "fieldPath")]
     pub field_path: Option<String>,
     #[serde(rename = "appendMissingElements")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub append_missing_elements: Option<ArrayValue>,
     #[serde(rename = "setToServerValue")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub set_to_server_value: Option<String>,
     #[serde(rename = "removeAllFromArray")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub remove_all_from_array: Option<ArrayValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub increment: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Value>,
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -441,6 +503,7 @@ pub struct ListCollectionIdsResponse {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct CommitRequest {
     pub writes: Option<Vec<Write>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction: Option<String>,
 }
 
@@ -459,6 +522,7 @@ pub struct ListDocumentsResponse {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ReadWrite {
     #[serde(rename = "retryTransaction")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub retry_transaction: Option<String>,
 }
 
@@ -503,11 +567,13 @@ pub struct RollbackRequest {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ReadOnly {
     #[serde(rename = "readTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub read_time: Option<String>,
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct BeginTransactionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<TransactionOptions>,
 }
 
@@ -529,7 +595,9 @@ pub struct WriteResult {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct QueryTarget {
     #[serde(rename = "structuredQuery")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub structured_query: Option<StructuredQuery>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parent: Option<String>,
 }
 
@@ -542,3 +610,191 @@ pub struct WriteRequest {
     #[serde(rename = "streamId")]
     pub stream_id: Option<String>,
 }
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct RunAggregationQueryRequest {
+    #[serde(rename = "structuredAggregationQuery")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_aggregation_query: Option<StructuredAggregationQuery>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+    #[serde(rename = "newTransaction")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_transaction: Option<TransactionOptions>,
+    #[serde(rename = "readTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_time: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct StructuredAggregationQuery {
+    #[serde(rename = "structuredQuery")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_query: Option<StructuredQuery>,
+    pub aggregations: Vec<Aggregation>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Aggregation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<Count>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sum: Option<Sum>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg: Option<Avg>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Count {
+    #[serde(rename = "upTo")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_to: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Sum {
+    pub field: FieldReference,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Avg {
+    pub field: FieldReference,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct RunAggregationQueryResponse {
+    pub result: Option<AggregationResult>,
+    pub transaction: Option<String>,
+    #[serde(rename = "readTime")]
+    pub read_time: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct AggregationResult {
+    #[serde(rename = "aggregateFields")]
+    pub aggregate_fields: Option<HashMap<String, Value>>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct PartitionQueryRequest {
+    #[serde(rename = "structuredQuery")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_query: Option<StructuredQuery>,
+    #[serde(rename = "partitionCount")]
+    pub partition_count: String,
+    #[serde(rename = "pageToken")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+    #[serde(rename = "pageSize")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<i32>,
+    #[serde(rename = "readTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_time: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct PartitionQueryResponse {
+    pub partitions: Option<Vec<Cursor>>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+}
+
+/// JSON has no NaN and infinite numbers. Firestore expects them as the strings "NaN", "Infinity" and "-Infinity".
+mod double_value {
+    use serde::{de, Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(v) if v.is_nan() => serializer.serialize_str("NaN"),
+            Some(v) if v.is_infinite() && v.is_sign_positive() => serializer.serialize_str("Infinity"),
+            Some(v) if v.is_infinite() => serializer.serialize_str("-Infinity"),
+            Some(v) => serializer.serialize_f64(*v),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+        deserializer.deserialize_any(DoubleVisitor)
+    }
+
+    struct DoubleVisitor;
+
+    impl<'de> de::Visitor<'de> for DoubleVisitor {
+        type Value = Option<f64>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number or one of \"NaN\", \"Infinity\" and \"-Infinity\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+            Ok(Some(v))
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+            Ok(Some(v as f64))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+            Ok(Some(v as f64))
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            match v {
+                "NaN" => Ok(Some(std::f64::NAN)),
+                "Infinity" => Ok(Some(std::f64::INFINITY)),
+                "-Infinity" => Ok(Some(std::f64::NEG_INFINITY)),
+                _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+    }
+}
+
+#[test]
+fn double_value_test() {
+    let values = [1.5, std::f64::NAN, std::f64::INFINITY, std::f64::NEG_INFINITY];
+    let json: Vec<serde_json::Value> = values
+        .iter()
+        .map(|v| {
+            serde_json::to_value(&Value {
+                double_value: Some(*v),
+                ..Default::default()
+            })
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        json,
+        vec![
+            serde_json::json!({ "doubleValue": 1.5 }),
+            serde_json::json!({ "doubleValue": "NaN" }),
+            serde_json::json!({ "doubleValue": "Infinity" }),
+            serde_json::json!({ "doubleValue": "-Infinity" }),
+        ]
+    );
+
+    let read = |json: serde_json::Value| serde_json::from_value::<Value>(json).unwrap().double_value;
+    assert_eq!(read(serde_json::json!({ "doubleValue": 2 })), Some(2.0));
+    assert_eq!(
+        read(serde_json::json!({ "doubleValue": "-Infinity" })),
+        Some(std::f64::NEG_INFINITY)
+    );
+    assert!(read(serde_json::json!({ "doubleValue": "NaN" })).unwrap().is_nan());
+    assert_eq!(read(serde_json::json!({ "stringValue": "a" })), None);
+    assert!(serde_json::from_value::<Value>(serde_json::json!({ "doubleValue": "abc" })).is_err());
+}
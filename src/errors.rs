@@ -27,6 +27,17 @@ pub enum FirebaseError {
     /// If the APIError happens on a user_* method, the user id will be set as context.
     /// For example: 400, CREDENTIAL_TOO_OLD_LOGIN_AGAIN
     APIError(usize, String, String),
+    /// A precondition of a write or delete did not hold, for example because the document has
+    /// been updated in the meantime. Contains the message and the document path as context.
+    ///
+    /// This is only returned for writes and deletes with an explicit precondition. Other errors with the
+    /// status FAILED_PRECONDITION, for example a query that requires a missing index, are an [`FirebaseError::APIError`].
+    FailedPrecondition(String, String),
+    /// Firestore aborted a transaction because of a concurrent modification. Contains the message and the context.
+    ///
+    /// This is only returned for the reads, the commit and the start of a transaction. Other requests report
+    /// the status ABORTED as [`FirebaseError::APIError`] with the code 409.
+    Aborted(String, String),
     /// An error caused by the http library. This only happens if the http request is badly
     /// formatted (too big, invalid characters) or if the server did strange things
     /// (connection abort, ssl verification error).
@@ -89,6 +100,10 @@ impl fmt::Display for FirebaseError {
             FirebaseError::APIError(code, ref m, ref context) => {
                 write!(f, "API Error! Code {} - {}. Context: {}", code, m, context)
             }
+            FirebaseError::FailedPrecondition(ref m, ref context) => {
+                write!(f, "Failed precondition! {}. Context: {}", m, context)
+            }
+            FirebaseError::Aborted(ref m, ref context) => write!(f, "Aborted! {}. Context: {}", m, context),
             FirebaseError::UnexpectedResponse(m, status, ref text, ref source) => {
                 writeln!(f, "{} - {}", &m, status)?;
                 writeln!(f, "{}", text)?;
@@ -117,6 +132,8 @@ impl error::Error for FirebaseError {
             FirebaseError::Generic(ref _m) => None,
             FirebaseError::UnexpectedResponse(_, _, _, _) => None,
             FirebaseError::APIError(_, _, _) => None,
+            FirebaseError::FailedPrecondition(_, _) => None,
+            FirebaseError::Aborted(_, _) => None,
             FirebaseError::Request(ref e) => Some(e),
             FirebaseError::JWT(ref e) => Some(e),
             FirebaseError::JWTValidation(ref e) => Some(e),
@@ -138,6 +155,7 @@ struct GoogleRESTApiError {
 struct GoogleRESTApiErrorInfo {
     pub code: usize,
     pub message: String,
+    pub status: Option<String>,
     pub errors: Option<Vec<GoogleRESTApiError>>,
 }
 
@@ -156,6 +174,16 @@ struct GoogleRESTApiErrorWrapper {
 pub(crate) fn extract_google_api_error(
     response: reqwest::blocking::Response,
     context: impl Fn() -> String,
+) -> Result<reqwest::blocking::Response> {
+    extract_google_api_error_with(response, context, |_, err| err)
+}
+
+/// Like [`extract_google_api_error`], but a Google API error is handed to `map_error` together with its status,
+/// for example "FAILED_PRECONDITION". It may return a more specific error.
+pub(crate) fn extract_google_api_error_with(
+    response: reqwest::blocking::Response,
+    context: impl Fn() -> String,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
 ) -> Result<reqwest::blocking::Response> {
     if response.status() == 200 {
         return Ok(response);
@@ -165,6 +193,7 @@ pub(crate) fn extract_google_api_error(
         response.status().clone(),
         response.text()?,
         context,
+        map_error,
     ))
 }
 
@@ -178,6 +207,16 @@ pub(crate) fn extract_google_api_error(
 pub(crate) async fn extract_google_api_error_async(
     response: reqwest::Response,
     context: impl Fn() -> String,
+) -> Result<reqwest::Response> {
+    extract_google_api_error_with_async(response, context, |_, err| err).await
+}
+
+/// [Async] Like [`extract_google_api_error_async`], but a Google API error is handed to `map_error` together with
+/// its status, for example "FAILED_PRECONDITION". It may return a more specific error.
+pub(crate) async fn extract_google_api_error_with_async(
+    response: reqwest::Response,
+    context: impl Fn() -> String,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
 ) -> Result<reqwest::Response> {
     if response.status() == 200 {
         return Ok(response);
@@ -187,6 +226,7 @@ pub(crate) async fn extract_google_api_error_async(
         response.status().clone(),
         response.text().await?,
         context,
+        map_error,
     ))
 }
 
@@ -194,14 +234,44 @@ fn extract_google_api_error_intern(
     status: StatusCode,
     http_body: String,
     context: impl Fn() -> String,
+    map_error: impl Fn(&str, FirebaseError) -> FirebaseError,
 ) -> FirebaseError {
     let google_api_error_wrapper: std::result::Result<GoogleRESTApiErrorWrapper, serde_json::Error> =
         serde_json::from_str(&http_body);
     if let Ok(google_api_error_wrapper) = google_api_error_wrapper {
         if let Some(google_api_error) = google_api_error_wrapper.error {
-            return FirebaseError::APIError(google_api_error.code, google_api_error.message.to_owned(), context());
+            let status = google_api_error.status.unwrap_or_default();
+            let err = FirebaseError::APIError(google_api_error.code, google_api_error.message.to_owned(), context());
+            return map_error(&status, err);
         }
     };
 
     FirebaseError::UnexpectedResponse("", status, http_body, context())
 }
+
+#[test]
+fn google_api_error_status_test() {
+    let error = |code: usize, status: &str| {
+        let body = serde_json::json!({ "error": { "code": code, "message": "m", "status": status } });
+        extract_google_api_error_intern(
+            StatusCode::CONFLICT,
+            body.to_string(),
+            || "ctx".to_owned(),
+            |_, err| err,
+        )
+    };
+    // Only transactions map ABORTED to FirebaseError::Aborted
+    match error(409, "ABORTED") {
+        FirebaseError::APIError(409, m, context) => assert_eq!((m.as_str(), context.as_str()), ("m", "ctx")),
+        err => panic!("Unexpected error {:?}", err),
+    }
+    match error(409, "ALREADY_EXISTS") {
+        FirebaseError::APIError(409, _, _) => {}
+        err => panic!("Unexpected error {:?}", err),
+    }
+    // A query that requires an index is not a failed precondition of a write
+    match error(400, "FAILED_PRECONDITION") {
+        FirebaseError::APIError(400, _, _) => {}
+        err => panic!("Unexpected error {:?}", err),
+    }
+}
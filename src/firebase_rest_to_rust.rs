@@ -4,118 +4,22 @@
 //! and deeply nested and wrapped.
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
 
 use super::dto;
 use super::errors::{FirebaseError, Result};
-
-#[derive(Serialize, Deserialize)]
-struct Wrapper {
-    #[serde(flatten)]
-    extra: HashMap<String, Value>,
-}
-
-use chrono::DateTime;
-use serde_json::{map::Map, Number};
-
-/// Converts a firebase google-rpc-api inspired heavily nested and wrapped response value
-/// of the Firebase REST API into a flattened serde json value.
-///
-/// This is a low level API. You probably want to use [`crate::documents`] instead.
-///
-/// This method works recursively!
-pub(crate) fn firebase_value_to_serde_value(v: &dto::Value) -> serde_json::Value {
-    if let Some(timestamp_value) = v.timestamp_value.as_ref() {
-        return Value::String(timestamp_value.clone());
-    } else if let Some(integer_value) = v.integer_value.as_ref() {
-        if let Ok(four) = integer_value.parse::<i64>() {
-            return Value::Number(four.into());
-        }
-    } else if let Some(double_value) = v.double_value {
-        if let Some(dd) = Number::from_f64(double_value) {
-            return Value::Number(dd);
-        }
-    } else if let Some(map_value) = v.map_value.as_ref() {
-        let mut map: Map<String, serde_json::value::Value> = Map::new();
-        for (map_key, map_v) in &map_value.fields {
-            map.insert(map_key.clone(), firebase_value_to_serde_value(&map_v));
-        }
-        return Value::Object(map);
-    } else if let Some(string_value) = v.string_value.as_ref() {
-        return Value::String(string_value.clone());
-    } else if let Some(boolean_value) = v.boolean_value {
-        return Value::Bool(boolean_value);
-    } else if let Some(array_value) = v.array_value.as_ref() {
-        let mut vec: Vec<Value> = Vec::new();
-        if let Some(values) = &array_value.values {
-            for k in values {
-                vec.push(firebase_value_to_serde_value(&k));
-            }
-        }
-        return Value::Array(vec);
-    }
-    Value::Null
-}
+use super::value_serde::{self, SerializeOptions};
 
 /// Converts a flat serde json value into a firebase google-rpc-api inspired heavily nested and wrapped type
 /// to be consumed by the Firebase REST API.
 ///
-/// This is a low level API. You probably want to use [`crate::documents`] instead.
+/// Objects with a single, reserved key are converted into timestamps, geo points, references or bytes,
+/// see [`crate::values`]. Strings that look like RFC3339 dates are converted into timestamps,
+/// see [`SerializeOptions::detect_timestamp_strings`].
 ///
-/// This method works recursively!
-pub(crate) fn serde_value_to_firebase_value(v: &serde_json::Value) -> dto::Value {
-    if v.is_f64() {
-        return dto::Value {
-            double_value: Some(v.as_f64().unwrap()),
-            ..Default::default()
-        };
-    } else if let Some(integer_value) = v.as_i64() {
-        return dto::Value {
-            integer_value: Some(integer_value.to_string()),
-            ..Default::default()
-        };
-    } else if let Some(map_value) = v.as_object() {
-        let mut map: HashMap<String, dto::Value> = HashMap::new();
-        for (map_key, map_v) in map_value {
-            map.insert(map_key.to_owned(), serde_value_to_firebase_value(&map_v));
-        }
-        return dto::Value {
-            map_value: Some(dto::MapValue { fields: map }),
-            ..Default::default()
-        };
-    } else if let Some(string_value) = v.as_str() {
-        return match DateTime::parse_from_rfc3339(string_value) {
-            Ok(_dt) => dto::Value {
-                timestamp_value: Some(string_value.to_owned()),
-                ..Default::default()
-            },
-            Err(_e) => dto::Value {
-                string_value: Some(string_value.to_owned()),
-                ..Default::default()
-            },
-        };
-    } else if let Some(boolean_value) = v.as_bool() {
-        return dto::Value {
-            boolean_value: Some(boolean_value),
-            ..Default::default()
-        };
-    } else if let Some(array_value) = v.as_array() {
-        let mut vec: Vec<dto::Value> = Vec::new();
-        for k in array_value {
-            vec.push(serde_value_to_firebase_value(&k));
-        }
-        return dto::Value {
-            array_value: Some(dto::ArrayValue { values: Some(vec) }),
-            ..Default::default()
-        };
-    } else if let Some(_) = v.as_null() {
-        return dto::Value {
-            null_value: Some(Value::Null),
-            ..Default::default()
-        };
-    }
-    Default::default()
+/// This is a low level API. You probably want to use [`crate::documents`] instead.
+/// This is a wrapper around [`value_serde::to_value`].
+pub(crate) fn serde_value_to_firebase_value(v: &serde_json::Value) -> Result<dto::Value> {
+    value_serde::to_value(v)
 }
 
 /// Converts a firebase google-rpc-api inspired heavily nested and wrapped response document
@@ -125,33 +29,15 @@ pub(crate) fn serde_value_to_firebase_value(v: &serde_json::Value) -> dto::Value
 ///
 /// Internals:
 ///
-/// This method uses recursion to decode the given firebase type.
+/// This method deserializes the document fields directly, see [`value_serde::ValueDeserializer`].
 pub fn document_to_pod<T>(document: &dto::Document) -> Result<T>
 where
     for<'de> T: Deserialize<'de>,
 {
-    // The firebase document has a field called "fields" that contain all top-level fields.
-    // We want those to be flattened to our custom data structure. To not reinvent the wheel,
-    // perform the firebase-value to serde-values conversion for all fields first and wrap those
-    // Wrapper struct with a HashMap. Use #[serde(flatten)] on that map.
-    let r = Wrapper {
-        extra: document
-            .fields
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|(k, v)| {
-                return (k.to_owned(), firebase_value_to_serde_value(&v));
-            })
-            .collect(),
-    };
-
-    let v = serde_json::to_value(r)?;
-    let r: T = serde_json::from_value(v).map_err(|e| FirebaseError::Ser {
+    value_serde::from_fields(document.fields.as_ref()).map_err(|e| FirebaseError::Ser {
         doc: Some(document.name.clone()),
         ser: e,
-    })?;
-    Ok(r)
+    })
 }
 
 /// Converts a custom data type into a firebase google-rpc-api inspired heavily nested and wrapped type
@@ -161,14 +47,28 @@ where
 ///
 /// Internals:
 ///
-/// This method uses recursion to decode the given firebase type.
+/// This method serializes the given type directly, see [`value_serde::ValueSerializer`].
 pub fn pod_to_document<T>(pod: &T) -> Result<dto::Document>
 where
     T: Serialize,
 {
-    let v = serde_json::to_value(pod)?;
+    pod_to_document_with(pod, SerializeOptions::default())
+}
+
+/// Like [`pod_to_document`], but with the given serializer options.
+/// For example, disable [`SerializeOptions::detect_timestamp_strings`] to store all strings as strings.
+pub fn pod_to_document_with<T>(pod: &T, options: SerializeOptions) -> Result<dto::Document>
+where
+    T: Serialize,
+{
+    let map_value = value_serde::to_value_with(pod, options)?
+        .map_value
+        .ok_or_else(|| FirebaseError::Ser {
+            doc: None,
+            ser: serde::ser::Error::custom("A document must be serialized as map"),
+        })?;
     Ok(dto::Document {
-        fields: Some(serde_value_to_firebase_value(&v).map_value.unwrap().fields),
+        fields: Some(map_value.fields),
         ..Default::default()
     })
 }
@@ -179,6 +79,7 @@ mod tests {
 
     use super::Result;
     use serde::{Deserialize, Serialize};
+    use serde_json::Value;
     use std::collections::HashMap;
 
     #[derive(Serialize, Deserialize)]
@@ -233,6 +134,110 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Serialize, Deserialize)]
+    struct ValuesPod {
+        timestamp: crate::values::Timestamp,
+        geo_point: crate::values::GeoPoint,
+        reference: crate::values::DocumentReference,
+        bytes: crate::values::Bytes,
+        date_string: String,
+    }
+
+    #[test]
+    fn test_values_roundtrip() -> Result<()> {
+        let t = ValuesPod {
+            timestamp: crate::values::Timestamp("2020-01-22T10:00:00.123456789Z".parse().unwrap()),
+            geo_point: crate::values::GeoPoint::new(52.5, 0.0),
+            reference: crate::values::DocumentReference::new("p", "users/u1"),
+            bytes: crate::values::Bytes(vec![0, 1, 255]),
+            date_string: "abc".to_owned(),
+        };
+        let mut firebase_doc = pod_to_document(&t)?;
+        {
+            let fields = firebase_doc.fields.as_mut().unwrap();
+            assert_eq!(
+                fields["timestamp"].timestamp_value.as_ref().unwrap(),
+                "2020-01-22T10:00:00.123456789Z"
+            );
+            assert_eq!(
+                fields["geo_point"].geo_point_value.as_ref().unwrap().latitude,
+                Some(52.5)
+            );
+            assert_eq!(
+                fields["reference"].reference_value.as_ref().unwrap(),
+                "projects/p/databases/(default)/documents/users/u1"
+            );
+            assert_eq!(fields["bytes"].bytes_value.as_ref().unwrap(), "AAH/");
+            // The REST API omits zero coordinates
+            fields
+                .get_mut("geo_point")
+                .unwrap()
+                .geo_point_value
+                .as_mut()
+                .unwrap()
+                .longitude = None;
+            // A timestamp can be read into a string field
+            fields.insert(
+                "date_string".to_owned(),
+                dto::Value {
+                    timestamp_value: Some("2020-01-22T10:00:00Z".to_owned()),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let r: ValuesPod = document_to_pod(&firebase_doc)?;
+        assert_eq!(r.timestamp, t.timestamp);
+        assert_eq!(r.geo_point, t.geo_point);
+        assert_eq!(r.reference.path(), "users/u1");
+        assert_eq!(r.bytes, t.bytes);
+        assert_eq!(r.date_string, "2020-01-22T10:00:00Z");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        let v = serde_json::json!({ "big": u64::max_value() });
+        assert!(pod_to_document(&v).is_err());
+        assert!(pod_to_document(&"not a map").is_err());
+        let v = serde_json::json!({ "$timestamp": "not a date" });
+        assert!(serde_value_to_firebase_value(&v).is_err());
+
+        let mut fields = HashMap::new();
+        fields.insert(
+            "big".to_owned(),
+            dto::Value {
+                integer_value: Some("18446744073709551615".to_owned()),
+                ..Default::default()
+            },
+        );
+        let t = dto::Document {
+            fields: Some(fields),
+            ..Default::default()
+        };
+        assert!(document_to_pod::<serde_json::Value>(&t).is_err());
+    }
+
+    #[derive(Deserialize)]
+    struct EmptyPod {
+        optional_test: Option<String>,
+    }
+
+    #[test]
+    fn test_document_without_fields_to_pod() -> Result<()> {
+        let t = dto::Document {
+            name: "projects/p/databases/(default)/documents/tests/empty".to_owned(),
+            fields: None,
+            ..Default::default()
+        };
+        let firebase_doc: EmptyPod = document_to_pod(&t)?;
+        assert_eq!(firebase_doc.optional_test, None);
+        assert!(document_to_pod::<DemoPod>(&t).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_pod_to_document() -> Result<()> {
         let t = DemoPod {
@@ -11,6 +11,8 @@ pub mod firebase_rest_to_rust;
 pub mod jwt;
 pub mod sessions;
 pub mod users;
+pub mod value_serde;
+pub mod values;
 
 #[cfg(feature = "rocket_support")]
 pub mod rocket;
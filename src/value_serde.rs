@@ -0,0 +1,1032 @@
+//! # Serde support for Firestore values
+//!
+//! A [`serde::Serializer`] that produces Firestore values ([`crate::dto::Value`]) and a [`serde::Deserializer`]
+//! that reads from them, without a detour via json.
+//!
+//! Integers are stored as 64 bit integers. Larger unsigned or 128 bit integers are only accepted if they fit
+//! and fail otherwise. Enums are represented like in serde_json: Unit variants as strings, all other variants
+//! as maps with the variant name as single key. Byte arrays, for example via the serde_bytes crate, are stored
+//! as bytes values. See [`crate::values`] for timestamps, geo points and references.
+//!
+//! Example:
+//! ```rust
+//! use firestore_db_and_auth::value_serde;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! enum Shape { Circle { radius: f64 }, Point }
+//!
+//! let value = value_serde::to_value(&vec![Shape::Circle { radius: 1.5 }, Shape::Point])?;
+//! let shapes: Vec<Shape> = value_serde::from_value(&value)?;
+//! assert_eq!(shapes, vec![Shape::Circle { radius: 1.5 }, Shape::Point]);
+//! # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+//! ```
+
+use super::dto;
+use super::errors::{FirebaseError, Result};
+use crate::values::{
+    BYTES_KEY, FIRESTORE_VALUE_TOKEN, GEO_POINT_KEY, PLAIN_STRING_TOKEN, REFERENCE_KEY, TIMESTAMP_KEY,
+};
+
+use chrono::DateTime;
+use serde::de::value::{BorrowedStrDeserializer, MapAccessDeserializer, MapDeserializer};
+use serde::de::{self, DeserializeSeed, Visitor};
+use serde::ser::{self, Serialize};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// The error type of the serializer and deserializer.
+/// It is reported as [`FirebaseError::Ser`] by [`to_value`] and [`from_value`].
+pub type Error = serde_json::Error;
+
+/// Options of the [`ValueSerializer`]
+#[derive(Clone, Copy, Debug)]
+pub struct SerializeOptions {
+    /// Store strings that look like RFC3339 dates as timestamps. This is enabled by default.
+    /// Disable this if string fields, like a display name, might contain such dates by accident.
+    /// [`crate::values::Timestamp`] fields are always stored as timestamps.
+    pub detect_timestamp_strings: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            detect_timestamp_strings: true,
+        }
+    }
+}
+
+/// Serializes the given value into a Firestore value
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<dto::Value> {
+    to_value_with(value, SerializeOptions::default())
+}
+
+/// Serializes the given value into a Firestore value with the given options
+pub fn to_value_with<T: Serialize + ?Sized>(value: &T, options: SerializeOptions) -> Result<dto::Value> {
+    value
+        .serialize(ValueSerializer::new(options))
+        .map_err(|ser| FirebaseError::Ser { doc: None, ser })
+}
+
+/// Deserializes an instance of type T from the given Firestore value
+pub fn from_value<'de, T: Deserialize<'de>>(value: &'de dto::Value) -> Result<T> {
+    T::deserialize(ValueDeserializer::new(value)).map_err(|ser| FirebaseError::Ser { doc: None, ser })
+}
+
+/// Deserializes an instance of type T from the given document fields. No fields are treated like an empty map.
+pub(crate) fn from_fields<'de, T: Deserialize<'de>>(
+    fields: Option<&'de HashMap<String, dto::Value>>,
+) -> std::result::Result<T, Error> {
+    T::deserialize(MapAccessDeserializer::new(FieldsAccess::new(
+        fields.into_iter().flatten(),
+    )))
+}
+
+#[inline]
+fn integer_value(v: i64) -> dto::Value {
+    dto::Value {
+        integer_value: Some(v.to_string()),
+        ..Default::default()
+    }
+}
+
+#[inline]
+fn map_value(fields: HashMap<String, dto::Value>) -> dto::Value {
+    dto::Value {
+        map_value: Some(dto::MapValue { fields }),
+        ..Default::default()
+    }
+}
+
+#[inline]
+fn array_value(values: Vec<dto::Value>) -> dto::Value {
+    dto::Value {
+        array_value: Some(dto::ArrayValue { values: Some(values) }),
+        ..Default::default()
+    }
+}
+
+#[inline]
+fn null_value() -> dto::Value {
+    dto::Value {
+        null_value: Some(serde_json::Value::Null),
+        ..Default::default()
+    }
+}
+
+/// A map with the variant name as single key
+#[inline]
+fn variant_value(variant: &str, value: dto::Value) -> dto::Value {
+    let mut fields = HashMap::with_capacity(1);
+    fields.insert(variant.to_owned(), value);
+    map_value(fields)
+}
+
+/// Converts integer types that do not always fit into 64 bit, or fails if they do not fit
+#[inline]
+fn integer_or_error<T>(v: T) -> std::result::Result<dto::Value, Error>
+where
+    i64: TryFrom<T>,
+{
+    i64::try_from(v)
+        .map(integer_value)
+        .map_err(|_| ser::Error::custom("The integer does not fit into 64 bit"))
+}
+
+/// Converts a map with a single, reserved key into the corresponding Firestore value. See [`crate::values`].
+/// Returns None for all other maps.
+fn marker_value(fields: &HashMap<String, dto::Value>) -> Option<std::result::Result<dto::Value, Error>> {
+    if fields.len() != 1 {
+        return None;
+    }
+    let (key, value) = fields.iter().next()?;
+    // A timestamp string might already have been detected as such
+    let string = value.string_value.as_ref().or(value.timestamp_value.as_ref());
+    let invalid = || ser::Error::custom(format!("Invalid {} value", key));
+    let result = match key.as_str() {
+        TIMESTAMP_KEY => string
+            .filter(|v| DateTime::parse_from_rfc3339(v).is_ok())
+            .map(|v| dto::Value {
+                timestamp_value: Some(v.to_owned()),
+                ..Default::default()
+            })
+            .ok_or_else(invalid),
+        REFERENCE_KEY => string
+            .map(|v| dto::Value {
+                reference_value: Some(v.to_owned()),
+                ..Default::default()
+            })
+            .ok_or_else(invalid),
+        BYTES_KEY => string
+            .map(|v| dto::Value {
+                bytes_value: Some(v.to_owned()),
+                ..Default::default()
+            })
+            .ok_or_else(invalid),
+        GEO_POINT_KEY => {
+            let coordinate = |name: &str| {
+                let v = value.map_value.as_ref()?.fields.get(name)?;
+                v.double_value.or_else(|| {
+                    v.integer_value
+                        .as_ref()
+                        .and_then(|i| i.parse::<i64>().ok())
+                        .map(|i| i as f64)
+                })
+            };
+            match (coordinate("latitude"), coordinate("longitude")) {
+                (Some(latitude), Some(longitude)) => Ok(dto::Value {
+                    geo_point_value: Some(dto::LatLng {
+                        latitude: Some(latitude),
+                        longitude: Some(longitude),
+                    }),
+                    ..Default::default()
+                }),
+                _ => Err(invalid()),
+            }
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// A [`serde::Serializer`] that produces a Firestore value. See [`to_value`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValueSerializer {
+    options: SerializeOptions,
+}
+
+impl ValueSerializer {
+    /// Creates a serializer with the given options
+    pub fn new(options: SerializeOptions) -> Self {
+        ValueSerializer { options }
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    fn serialize_bool(self, v: bool) -> std::result::Result<dto::Value, Error> {
+        Ok(dto::Value {
+            boolean_value: Some(v),
+            ..Default::default()
+        })
+    }
+
+    fn serialize_i8(self, v: i8) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> std::result::Result<dto::Value, Error> {
+        integer_or_error(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> std::result::Result<dto::Value, Error> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> std::result::Result<dto::Value, Error> {
+        integer_or_error(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> std::result::Result<dto::Value, Error> {
+        integer_or_error(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> std::result::Result<dto::Value, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> std::result::Result<dto::Value, Error> {
+        Ok(dto::Value {
+            double_value: Some(v),
+            ..Default::default()
+        })
+    }
+
+    fn serialize_char(self, v: char) -> std::result::Result<dto::Value, Error> {
+        self.serialize_str(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> std::result::Result<dto::Value, Error> {
+        if self.options.detect_timestamp_strings && DateTime::parse_from_rfc3339(v).is_ok() {
+            return Ok(dto::Value {
+                timestamp_value: Some(v.to_owned()),
+                ..Default::default()
+            });
+        }
+        Ok(dto::Value {
+            string_value: Some(v.to_owned()),
+            ..Default::default()
+        })
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> std::result::Result<dto::Value, Error> {
+        Ok(dto::Value {
+            bytes_value: Some(base64::encode(v)),
+            ..Default::default()
+        })
+    }
+
+    fn serialize_none(self) -> std::result::Result<dto::Value, Error> {
+        Ok(null_value())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> std::result::Result<dto::Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> std::result::Result<dto::Value, Error> {
+        Ok(null_value())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> std::result::Result<dto::Value, Error> {
+        Ok(null_value())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> std::result::Result<dto::Value, Error> {
+        Ok(dto::Value {
+            string_value: Some(variant.to_owned()),
+            ..Default::default()
+        })
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> std::result::Result<dto::Value, Error> {
+        let value = value.serialize(self)?;
+        // Strings of a FirestoreValue are never detected as timestamps
+        if name == PLAIN_STRING_TOKEN {
+            if let Some(timestamp_value) = value.timestamp_value {
+                return Ok(dto::Value {
+                    string_value: Some(timestamp_value),
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> std::result::Result<dto::Value, Error> {
+        Ok(variant_value(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> std::result::Result<SerializeArray, Error> {
+        Ok(SerializeArray {
+            values: Vec::with_capacity(len.unwrap_or_default()),
+            options: self.options,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> std::result::Result<SerializeArray, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> std::result::Result<SerializeArray, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> std::result::Result<SerializeTupleVariant, Error> {
+        Ok(SerializeTupleVariant {
+            variant,
+            array: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> std::result::Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            fields: HashMap::with_capacity(len.unwrap_or_default()),
+            next_key: None,
+            options: self.options,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> std::result::Result<SerializeMap, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> std::result::Result<SerializeStructVariant, Error> {
+        Ok(SerializeStructVariant {
+            variant,
+            map: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+/// Serializes sequences and tuples into an array value
+pub struct SerializeArray {
+    values: Vec<dto::Value>,
+    options: SerializeOptions,
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.values.push(value.serialize(ValueSerializer::new(self.options))?);
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        Ok(array_value(self.values))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes a tuple variant into a map with the variant name as key and an array value
+pub struct SerializeTupleVariant {
+    variant: &'static str,
+    array: SerializeArray,
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        ser::SerializeSeq::serialize_element(&mut self.array, value)
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        Ok(variant_value(self.variant, ser::SerializeSeq::end(self.array)?))
+    }
+}
+
+/// Serializes maps and structs into a map value
+pub struct SerializeMap {
+    fields: HashMap<String, dto::Value>,
+    next_key: Option<String>,
+    options: SerializeOptions,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> std::result::Result<(), Error> {
+        let key = key.serialize(ValueSerializer::new(self.options))?;
+        // Like in json, keys are strings. Integer keys are converted.
+        let key = key
+            .string_value
+            .or(key.timestamp_value)
+            .or(key.integer_value)
+            .ok_or_else(|| ser::Error::custom("Map keys must be strings or integers"))?;
+        self.next_key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| ser::Error::custom("serialize_value called before serialize_key"))?;
+        self.fields
+            .insert(key, value.serialize(ValueSerializer::new(self.options))?);
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        match marker_value(&self.fields) {
+            Some(value) => value,
+            None => Ok(map_value(self.fields)),
+        }
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), Error> {
+        self.fields
+            .insert(key.to_owned(), value.serialize(ValueSerializer::new(self.options))?);
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        Ok(map_value(self.fields))
+    }
+}
+
+/// Serializes a struct variant into a map with the variant name as key and a map value
+pub struct SerializeStructVariant {
+    variant: &'static str,
+    map: SerializeMap,
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = dto::Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.map, key, value)
+    }
+
+    fn end(self) -> std::result::Result<dto::Value, Error> {
+        Ok(variant_value(self.variant, ser::SerializeStruct::end(self.map)?))
+    }
+}
+
+/// Returns true for the null value. The REST API sends `"nullValue": null`, which is deserialized
+/// into a value without any variant. Such a value is null as well.
+fn is_null(v: &dto::Value) -> bool {
+    v.null_value.is_some()
+        || (v.bytes_value.is_none()
+            && v.timestamp_value.is_none()
+            && v.geo_point_value.is_none()
+            && v.reference_value.is_none()
+            && v.double_value.is_none()
+            && v.map_value.is_none()
+            && v.string_value.is_none()
+            && v.boolean_value.is_none()
+            && v.array_value.is_none()
+            && v.integer_value.is_none())
+}
+
+/// A [`serde::Deserializer`] that reads from a Firestore value. See [`from_value`].
+///
+/// Strings are borrowed from the value, if the target type supports it.
+/// Timestamps are read as strings. Geo points, references and bytes are read as maps with a single,
+/// reserved key, that are understood by the types of [`crate::values`].
+pub struct ValueDeserializer<'de> {
+    value: &'de dto::Value,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    /// Creates a deserializer for the given Firestore value
+    pub fn new(value: &'de dto::Value) -> Self {
+        ValueDeserializer { value }
+    }
+}
+
+fn parse_integer(integer_value: &str) -> std::result::Result<i64, Error> {
+    integer_value
+        .parse()
+        .map_err(|_| de::Error::custom(format!("Invalid integer value {}", integer_value)))
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+        let v = self.value;
+        if let Some(timestamp_value) = v.timestamp_value.as_ref() {
+            visitor.visit_borrowed_str(timestamp_value)
+        } else if let Some(integer_value) = v.integer_value.as_ref() {
+            visitor.visit_i64(parse_integer(integer_value)?)
+        } else if let Some(double_value) = v.double_value {
+            visitor.visit_f64(double_value)
+        } else if let Some(map_value) = v.map_value.as_ref() {
+            visitor.visit_map(FieldsAccess::new(map_value.fields.iter()))
+        } else if let Some(string_value) = v.string_value.as_ref() {
+            visitor.visit_borrowed_str(string_value)
+        } else if let Some(boolean_value) = v.boolean_value {
+            visitor.visit_bool(boolean_value)
+        } else if let Some(array_value) = v.array_value.as_ref() {
+            visitor.visit_seq(ArrayAccess {
+                values: array_value.values.iter().flatten(),
+            })
+        } else if let Some(bytes_value) = v.bytes_value.as_ref() {
+            visitor.visit_map(MarkerAccess::new(BYTES_KEY, MarkerValue::Str(bytes_value)))
+        } else if let Some(reference_value) = v.reference_value.as_ref() {
+            visitor.visit_map(MarkerAccess::new(REFERENCE_KEY, MarkerValue::Str(reference_value)))
+        } else if let Some(geo_point_value) = v.geo_point_value.as_ref() {
+            // Zero coordinates are omitted by the REST API
+            let point = MarkerValue::GeoPoint(
+                geo_point_value.latitude.unwrap_or_default(),
+                geo_point_value.longitude.unwrap_or_default(),
+            );
+            visitor.visit_map(MarkerAccess::new(GEO_POINT_KEY, point))
+        } else {
+            visitor.visit_unit()
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+        if is_null(self.value) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+        match self.value.bytes_value.as_ref() {
+            Some(bytes_value) => visitor.visit_byte_buf(base64::decode(bytes_value).map_err(de::Error::custom)?),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        // A FirestoreValue distinguishes timestamps from strings
+        if name == FIRESTORE_VALUE_TOKEN {
+            return match self.value.timestamp_value.as_ref() {
+                Some(timestamp_value) => {
+                    visitor.visit_map(MarkerAccess::new(TIMESTAMP_KEY, MarkerValue::Str(timestamp_value)))
+                }
+                None => self.deserialize_any(visitor),
+            };
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        if let Some(string_value) = self.value.string_value.as_ref() {
+            return visitor.visit_enum(BorrowedStrDeserializer::new(string_value));
+        }
+        match self.value.map_value.as_ref() {
+            Some(map_value) if map_value.fields.len() == 1 => {
+                let (variant, value) = map_value.fields.iter().next().unwrap();
+                visitor.visit_enum(EnumAccess { variant, value })
+            }
+            _ => Err(de::Error::custom("An enum must be a string or a map with a single key")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Iterates over the fields of a map value
+struct FieldsAccess<'de, I> {
+    fields: I,
+    value: Option<&'de dto::Value>,
+}
+
+impl<'de, I> FieldsAccess<'de, I> {
+    fn new(fields: I) -> Self {
+        FieldsAccess { fields, value: None }
+    }
+}
+
+impl<'de, I> de::MapAccess<'de> for FieldsAccess<'de, I>
+where
+    I: Iterator<Item = (&'de String, &'de dto::Value)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Error> {
+        match self.fields.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer { key }).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> std::result::Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("next_value_seed called before next_key_seed"))?;
+        seed.deserialize(ValueDeserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.fields.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// Deserializes a map key. Like in json, integer keys are parsed from their string representation.
+struct KeyDeserializer<'de> {
+    key: &'de str,
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+                match self.key.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => visitor.visit_borrowed_str(self.key),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.key)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.key))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Iterates over the values of an array value
+struct ArrayAccess<I> {
+    values: I,
+}
+
+impl<'de, I> de::SeqAccess<'de> for ArrayAccess<I>
+where
+    I: Iterator<Item = &'de dto::Value>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> std::result::Result<Option<T::Value>, Error> {
+        match self.values.next() {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.values.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a map with a single, reserved key. See [`crate::values`].
+enum MarkerValue<'de> {
+    Str(&'de str),
+    GeoPoint(f64, f64),
+}
+
+/// A map with a single, reserved key
+struct MarkerAccess<'de> {
+    key: Option<&'static str>,
+    value: Option<MarkerValue<'de>>,
+}
+
+impl<'de> MarkerAccess<'de> {
+    fn new(key: &'static str, value: MarkerValue<'de>) -> Self {
+        MarkerAccess {
+            key: Some(key),
+            value: Some(value),
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MarkerAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Error> {
+        match self.key.take() {
+            Some(key) => seed.deserialize(BorrowedStrDeserializer::new(key)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> std::result::Result<V::Value, Error> {
+        match self.value.take() {
+            Some(MarkerValue::Str(value)) => seed.deserialize(BorrowedStrDeserializer::new(value)),
+            Some(MarkerValue::GeoPoint(latitude, longitude)) => {
+                let fields = vec![("latitude", latitude), ("longitude", longitude)];
+                seed.deserialize(MapDeserializer::new(fields.into_iter()))
+            }
+            None => Err(de::Error::custom("next_value_seed called before next_key_seed")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.key.iter().count())
+    }
+}
+
+/// An enum variant with content, stored as map with the variant name as single key
+struct EnumAccess<'de> {
+    variant: &'de str,
+    value: &'de dto::Value,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+    type Variant = ValueDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> std::result::Result<(V::Value, ValueDeserializer<'de>), Error> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::new(self.variant))?;
+        Ok((variant, ValueDeserializer::new(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> std::result::Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> std::result::Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[test]
+fn value_serde_test() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Line(i32, i32),
+        Rect { w: u64, h: u64 },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Pod<'a> {
+        big: u64,
+        wide: i128,
+        small: i8,
+        borrowed: &'a str,
+        shapes: Vec<Shape>,
+        nothing: Option<bool>,
+        map: HashMap<u32, String>,
+    }
+
+    let pod = Pod {
+        big: i64::max_value() as u64,
+        wide: i64::min_value() as i128,
+        small: -3,
+        borrowed: "abc",
+        shapes: vec![
+            Shape::Point,
+            Shape::Circle(0.5),
+            Shape::Line(1, 2),
+            Shape::Rect { w: 3, h: 4 },
+        ],
+        nothing: None,
+        map: vec![(7, "seven".to_owned())].into_iter().collect(),
+    };
+    let value = to_value(&pod).unwrap();
+    let fields = &value.map_value.as_ref().unwrap().fields;
+    assert_eq!(fields["big"].integer_value.as_ref().unwrap(), "9223372036854775807");
+    assert_eq!(
+        fields["shapes"].array_value.as_ref().unwrap().values.as_ref().unwrap()[0].string_value,
+        Some("Point".to_owned())
+    );
+    assert!(fields["nothing"].null_value.is_some());
+    assert!(fields["map"].map_value.as_ref().unwrap().fields.contains_key("7"));
+
+    let read: Pod = from_value(&value).unwrap();
+    assert_eq!(read, pod);
+
+    assert!(to_value(&u64::max_value()).is_err());
+    assert!(to_value(&(i64::max_value() as i128 + 1)).is_err());
+    let bool_key_map: HashMap<bool, bool> = vec![(true, true)].into_iter().collect();
+    assert!(to_value(&bool_key_map).is_err());
+
+    let date = "2020-01-22T10:00:00Z";
+    assert_eq!(to_value(date).unwrap().timestamp_value, Some(date.to_owned()));
+    let options = SerializeOptions {
+        detect_timestamp_strings: false,
+    };
+    let value = to_value_with(&vec![date], options).unwrap();
+    let values = value.array_value.unwrap().values.unwrap();
+    assert_eq!(values[0].string_value, Some(date.to_owned()));
+}
+
+#[test]
+fn value_serde_null_test() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Nullable {
+        a: Option<i64>,
+        b: Option<String>,
+    }
+
+    let value: dto::Value = serde_json::from_value(serde_json::json!({
+        "mapValue": { "fields": {
+            "a": { "nullValue": null },
+            "b": { "stringValue": "b" }
+        } }
+    }))
+    .unwrap();
+    let read: Nullable = from_value(&value).unwrap();
+    assert_eq!(
+        read,
+        Nullable {
+            a: None,
+            b: Some("b".to_owned())
+        }
+    );
+}
+
+#[test]
+fn value_serde_bytes_test() {
+    struct RawBytes(Vec<u8>);
+
+    impl Serialize for RawBytes {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for RawBytes {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+            struct BytesVisitor;
+            impl<'de> Visitor<'de> for BytesVisitor {
+                type Value = RawBytes;
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("bytes")
+                }
+                fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<RawBytes, E> {
+                    Ok(RawBytes(v))
+                }
+            }
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+
+    let value = to_value(&RawBytes(vec![0, 1, 255])).unwrap();
+    assert_eq!(value.bytes_value.as_ref().unwrap(), "AAH/");
+    let read: RawBytes = from_value(&value).unwrap();
+    assert_eq!(read.0, vec![0, 1, 255]);
+}
@@ -0,0 +1,772 @@
+//! # Firestore value types
+//!
+//! Firestore knows a few value types that have no direct counterpart in json:
+//! Timestamps, geo points, document references and bytes.
+//! Use the types of this module as fields of your document structs to read and write them
+//! without any loss of information.
+//!
+//! Example:
+//! ```rust
+//! use firestore_db_and_auth::values::{Bytes, DocumentReference, GeoPoint, Timestamp};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Place {
+//!     name: String,
+//!     location: GeoPoint,
+//!     owner: DocumentReference,
+//!     thumbnail: Bytes,
+//!     created: Timestamp,
+//! }
+//! ```
+//!
+//! Within the json representation that is used by [`crate::firebase_rest_to_rust`], those values
+//! are represented as objects with a single, reserved key, for example `{ "$timestamp": "2020-01-22T10:00:00Z" }`.
+//! The reserved keys are `$timestamp`, `$geo_point`, `$reference` and `$bytes`. Any map with exactly one of
+//! those keys is written as the corresponding value type, so do not use them as keys of your own maps.
+//!
+//! Documents without a fixed schema can be read and written as [`FirestoreValue`].
+
+use crate::documents::split_field_path;
+use crate::dto;
+use crate::errors::{FirebaseError, Result};
+
+use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// The reserved key of a timestamp value in its json representation
+pub(crate) const TIMESTAMP_KEY: &str = "$timestamp";
+/// The reserved key of a geo point value in its json representation
+pub(crate) const GEO_POINT_KEY: &str = "$geo_point";
+/// The reserved key of a document reference value in its json representation
+pub(crate) const REFERENCE_KEY: &str = "$reference";
+/// The reserved key of a bytes value in its json representation
+pub(crate) const BYTES_KEY: &str = "$bytes";
+/// The newtype struct name that a [`FirestoreValue`] is deserialized as.
+/// This tells the deserializer of [`crate::value_serde`] to distinguish timestamps from strings.
+pub(crate) const FIRESTORE_VALUE_TOKEN: &str = "$firestore_value";
+/// The newtype struct name of a string of a [`FirestoreValue`], that is never detected as timestamp
+pub(crate) const PLAIN_STRING_TOKEN: &str = "$plain_string";
+
+/// Serializes a value as a map with a single, reserved key
+fn serialize_marker<S: Serializer, V: Serialize + ?Sized>(
+    serializer: S,
+    key: &str,
+    value: &V,
+) -> std::result::Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(key, value)?;
+    map.end()
+}
+
+/// A Firestore timestamp with nanosecond precision.
+///
+/// Strings that look like RFC3339 dates are stored as timestamps as well, unless this has been disabled via
+/// [`crate::value_serde::SerializeOptions::detect_timestamp_strings`]. This type is always stored as a timestamp.
+/// Reading a timestamp into a `String` or a `chrono::DateTime` field works, too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub DateTime<Utc>);
+
+impl Timestamp {
+    /// The current time
+    pub fn now() -> Self {
+        Timestamp(Utc::now())
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(date_time: DateTime<Utc>) -> Self {
+        Timestamp(date_time)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let value = self.0.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true);
+        serialize_marker(serializer, TIMESTAMP_KEY, &value)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TimestampRepr {
+    Plain(String),
+    Marker {
+        #[serde(rename = "$timestamp")]
+        value: String,
+    },
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = match TimestampRepr::deserialize(deserializer)? {
+            TimestampRepr::Plain(value) | TimestampRepr::Marker { value } => value,
+        };
+        let date_time = DateTime::parse_from_rfc3339(&value).map_err(de::Error::custom)?;
+        Ok(Timestamp(date_time.with_timezone(&Utc)))
+    }
+}
+
+/// A geographical point, given by its latitude and longitude in degrees
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GeoPoint {
+    /// The latitude in degrees, within the range [-90.0, +90.0]
+    pub latitude: f64,
+    /// The longitude in degrees, within the range [-180.0, +180.0]
+    pub longitude: f64,
+}
+
+impl GeoPoint {
+    /// Creates a geo point of the given latitude and longitude in degrees
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        GeoPoint { latitude, longitude }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GeoPointFields {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Deserialize)]
+struct GeoPointMarker {
+    #[serde(rename = "$geo_point")]
+    value: GeoPointFields,
+}
+
+impl Serialize for GeoPoint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let value = GeoPointFields {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        };
+        serialize_marker(serializer, GEO_POINT_KEY, &value)
+    }
+}
+
+impl<'de> Deserialize<'de> for GeoPoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = GeoPointMarker::deserialize(deserializer)?.value;
+        Ok(GeoPoint::new(value.latitude, value.longitude))
+    }
+}
+
+/// A reference to another document, given by its absolute document name.
+///
+/// The document name has the form "projects/{project_id}/databases/(default)/documents/{document_path}".
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DocumentReference(pub String);
+
+impl DocumentReference {
+    /// Creates a reference to the document with the given relative path, for example "my_collection/document_id"
+    pub fn new(project_id: &str, path: &str) -> Self {
+        DocumentReference(format!(
+            "projects/{}/databases/(default)/documents/{}",
+            project_id, path
+        ))
+    }
+
+    /// The absolute document name
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// The relative document path, for example "my_collection/document_id"
+    pub fn path(&self) -> &str {
+        crate::documents::abs_to_rel(&self.0)
+    }
+}
+
+impl Serialize for DocumentReference {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_marker(serializer, REFERENCE_KEY, &self.0)
+    }
+}
+
+#[derive(Deserialize)]
+struct ReferenceMarker {
+    #[serde(rename = "$reference")]
+    value: String,
+}
+
+impl<'de> Deserialize<'de> for DocumentReference {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(DocumentReference(ReferenceMarker::deserialize(deserializer)?.value))
+    }
+}
+
+/// A byte array. In contrast to a `Vec<u8>`, which is stored as array of integers, this is stored as bytes value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes(bytes)
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serialize_marker(serializer, BYTES_KEY, &base64::encode(&self.0))
+    }
+}
+
+#[derive(Deserialize)]
+struct BytesMarker {
+    #[serde(rename = "$bytes")]
+    value: String,
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = BytesMarker::deserialize(deserializer)?.value;
+        Ok(Bytes(base64::decode(&value).map_err(de::Error::custom)?))
+    }
+}
+
+///
+/// A Firestore value of any type. Use it for documents without a fixed schema.
+///
+/// A document can be read as `FirestoreValue` (a [`FirestoreValue::Map`] of all fields) and be written again
+/// without any loss of information. Nested fields are accessed via field paths, for example "a_map.b.c".
+/// The only exception are maps with a single, reserved key like `$timestamp` (see [`crate::values`]),
+/// which are written as the corresponding value type instead of a map.
+///
+/// Example:
+/// ```rust
+/// use firestore_db_and_auth::values::FirestoreValue;
+///
+/// let mut doc = FirestoreValue::Map(Default::default());
+/// doc.set("a_map.an_int", 12)?;
+/// doc.set("a_map.`some.key`", "abc")?;
+/// assert_eq!(doc.get("a_map.an_int").and_then(|v| v.as_i64()), Some(12));
+/// assert_eq!(doc.get("a_map.`some.key`").and_then(|v| v.as_str()), Some("abc"));
+///
+/// let value: i64 = doc.remove("a_map.an_int").unwrap().try_into_i64()?;
+/// assert_eq!(value, 12);
+/// # Ok::<(), firestore_db_and_auth::errors::FirebaseError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum FirestoreValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Double(f64),
+    Timestamp(DateTime<Utc>),
+    String(String),
+    Bytes(Vec<u8>),
+    /// A reference to another document, given by its absolute document name
+    Reference(String),
+    GeoPoint(GeoPoint),
+    Array(Vec<FirestoreValue>),
+    Map(HashMap<String, FirestoreValue>),
+}
+
+impl Default for FirestoreValue {
+    fn default() -> Self {
+        FirestoreValue::Null
+    }
+}
+
+impl FirestoreValue {
+    /// Returns the value at the given field path, for example "a_map.b.c".
+    /// Field names that contain dots must be quoted with backticks, see [`crate::documents::split_field_path`].
+    pub fn get(&self, field_path: &str) -> Option<&FirestoreValue> {
+        split_field_path(field_path)
+            .iter()
+            .try_fold(self, |value, name| match value {
+                FirestoreValue::Map(map) => map.get(name),
+                _ => None,
+            })
+    }
+
+    /// Returns the value at the given field path mutably. See [`FirestoreValue::get`].
+    pub fn get_mut(&mut self, field_path: &str) -> Option<&mut FirestoreValue> {
+        split_field_path(field_path)
+            .iter()
+            .try_fold(self, |value, name| match value {
+                FirestoreValue::Map(map) => map.get_mut(name),
+                _ => None,
+            })
+    }
+
+    /// Sets the value at the given field path, for example "a_map.b.c".
+    /// Missing maps on the way are created. Fails if a value on the way is not a map.
+    pub fn set(&mut self, field_path: &str, value: impl Into<FirestoreValue>) -> Result<()> {
+        let mut names = split_field_path(field_path);
+        let last = names.pop().unwrap_or_default();
+        let mut current = self;
+        for name in names {
+            current = match current {
+                FirestoreValue::Map(map) => map.entry(name).or_insert_with(|| FirestoreValue::Map(HashMap::new())),
+                _ => {
+                    return Err(FirebaseError::Generic(
+                        "The field path refers to a field of a non-map value",
+                    ))
+                }
+            };
+        }
+        match current {
+            FirestoreValue::Map(map) => {
+                map.insert(last, value.into());
+                Ok(())
+            }
+            _ => Err(FirebaseError::Generic(
+                "The field path refers to a field of a non-map value",
+            )),
+        }
+    }
+
+    /// Removes the value at the given field path and returns it
+    pub fn remove(&mut self, field_path: &str) -> Option<FirestoreValue> {
+        let mut names = split_field_path(field_path);
+        let last = names.pop()?;
+        let parent = names.iter().try_fold(self, |value, name| match value {
+            FirestoreValue::Map(map) => map.get_mut(name),
+            _ => None,
+        })?;
+        match parent {
+            FirestoreValue::Map(map) => map.remove(&last),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is a null value
+    pub fn is_null(&self) -> bool {
+        *self == FirestoreValue::Null
+    }
+
+    /// Returns the value of a boolean
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FirestoreValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an integer. Doubles are not converted.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            FirestoreValue::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns doubles and integers as f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FirestoreValue::Double(v) => Some(*v),
+            FirestoreValue::Integer(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a string. Timestamps are not converted.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FirestoreValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a timestamp
+    pub fn as_timestamp(&self) -> Option<DateTime<Utc>> {
+        match self {
+            FirestoreValue::Timestamp(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a bytes value, already base64 decoded
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            FirestoreValue::Bytes(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the absolute document name of a reference
+    pub fn as_reference(&self) -> Option<&str> {
+        match self {
+            FirestoreValue::Reference(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a geo point
+    pub fn as_geo_point(&self) -> Option<GeoPoint> {
+        match self {
+            FirestoreValue::GeoPoint(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of an array
+    pub fn as_array(&self) -> Option<&Vec<FirestoreValue>> {
+        match self {
+            FirestoreValue::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of a map
+    pub fn as_map(&self) -> Option<&HashMap<String, FirestoreValue>> {
+        match self {
+            FirestoreValue::Map(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Converts this value into an i64, or fails if it is not an integer. See also the [`TryFrom`] implementations.
+    pub fn try_into_i64(self) -> Result<i64> {
+        i64::try_from(self)
+    }
+}
+
+macro_rules! firestore_value_from {
+    ($($from:ty => $variant:ident,)*) => {
+        $(
+            impl From<$from> for FirestoreValue {
+                fn from(v: $from) -> Self {
+                    FirestoreValue::$variant(v.into())
+                }
+            }
+        )*
+    };
+}
+
+firestore_value_from! {
+    bool => Bool,
+    i32 => Integer,
+    i64 => Integer,
+    u32 => Integer,
+    f64 => Double,
+    DateTime<Utc> => Timestamp,
+    String => String,
+    &str => String,
+    Vec<u8> => Bytes,
+    GeoPoint => GeoPoint,
+    Vec<FirestoreValue> => Array,
+    HashMap<String, FirestoreValue> => Map,
+}
+
+impl From<Timestamp> for FirestoreValue {
+    fn from(v: Timestamp) -> Self {
+        FirestoreValue::Timestamp(v.0)
+    }
+}
+
+impl From<Bytes> for FirestoreValue {
+    fn from(v: Bytes) -> Self {
+        FirestoreValue::Bytes(v.0)
+    }
+}
+
+impl From<DocumentReference> for FirestoreValue {
+    fn from(v: DocumentReference) -> Self {
+        FirestoreValue::Reference(v.0)
+    }
+}
+
+impl<T: Into<FirestoreValue>> From<Option<T>> for FirestoreValue {
+    fn from(v: Option<T>) -> Self {
+        v.map(Into::into).unwrap_or(FirestoreValue::Null)
+    }
+}
+
+macro_rules! firestore_value_try_into {
+    ($($into:ty => $variant:ident,)*) => {
+        $(
+            impl TryFrom<FirestoreValue> for $into {
+                type Error = FirebaseError;
+
+                fn try_from(v: FirestoreValue) -> Result<Self> {
+                    match v {
+                        FirestoreValue::$variant(v) => Ok(v),
+                        _ => Err(FirebaseError::Generic(concat!("The value is not of type ", stringify!($variant)))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+firestore_value_try_into! {
+    bool => Bool,
+    i64 => Integer,
+    f64 => Double,
+    DateTime<Utc> => Timestamp,
+    String => String,
+    Vec<u8> => Bytes,
+    GeoPoint => GeoPoint,
+    Vec<FirestoreValue> => Array,
+    HashMap<String, FirestoreValue> => Map,
+}
+
+impl TryFrom<&dto::Value> for FirestoreValue {
+    type Error = FirebaseError;
+
+    /// Converts a REST API value without any loss of information.
+    /// Fails for malformed timestamps, integers or bytes.
+    fn try_from(v: &dto::Value) -> Result<Self> {
+        let invalid = |_| FirebaseError::Generic("Invalid value in a Firestore document");
+        Ok(if let Some(timestamp_value) = v.timestamp_value.as_ref() {
+            FirestoreValue::Timestamp(
+                DateTime::parse_from_rfc3339(timestamp_value)
+                    .map_err(|_| FirebaseError::Generic("Invalid timestamp value in a Firestore document"))?
+                    .with_timezone(&Utc),
+            )
+        } else if let Some(integer_value) = v.integer_value.as_ref() {
+            FirestoreValue::Integer(integer_value.parse().map_err(invalid)?)
+        } else if let Some(double_value) = v.double_value {
+            FirestoreValue::Double(double_value)
+        } else if let Some(map_value) = v.map_value.as_ref() {
+            FirestoreValue::Map(
+                map_value
+                    .fields
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), FirestoreValue::try_from(v)?)))
+                    .collect::<Result<_>>()?,
+            )
+        } else if let Some(string_value) = v.string_value.as_ref() {
+            FirestoreValue::String(string_value.clone())
+        } else if let Some(boolean_value) = v.boolean_value {
+            FirestoreValue::Bool(boolean_value)
+        } else if let Some(array_value) = v.array_value.as_ref() {
+            FirestoreValue::Array(
+                array_value
+                    .values
+                    .iter()
+                    .flatten()
+                    .map(FirestoreValue::try_from)
+                    .collect::<Result<_>>()?,
+            )
+        } else if let Some(bytes_value) = v.bytes_value.as_ref() {
+            FirestoreValue::Bytes(
+                base64::decode(bytes_value)
+                    .map_err(|_| FirebaseError::Generic("Invalid bytes value in a Firestore document"))?,
+            )
+        } else if let Some(reference_value) = v.reference_value.as_ref() {
+            FirestoreValue::Reference(reference_value.clone())
+        } else if let Some(geo_point_value) = v.geo_point_value.as_ref() {
+            // Zero coordinates are omitted by the REST API
+            FirestoreValue::GeoPoint(GeoPoint::new(
+                geo_point_value.latitude.unwrap_or_default(),
+                geo_point_value.longitude.unwrap_or_default(),
+            ))
+        } else {
+            FirestoreValue::Null
+        })
+    }
+}
+
+impl From<FirestoreValue> for dto::Value {
+    fn from(v: FirestoreValue) -> Self {
+        let mut value = dto::Value::default();
+        match v {
+            FirestoreValue::Null => value.null_value = Some(serde_json::Value::Null),
+            FirestoreValue::Bool(v) => value.boolean_value = Some(v),
+            FirestoreValue::Integer(v) => value.integer_value = Some(v.to_string()),
+            FirestoreValue::Double(v) => value.double_value = Some(v),
+            FirestoreValue::Timestamp(v) => {
+                value.timestamp_value = Some(v.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true))
+            }
+            FirestoreValue::String(v) => value.string_value = Some(v),
+            FirestoreValue::Bytes(v) => value.bytes_value = Some(base64::encode(&v)),
+            FirestoreValue::Reference(v) => value.reference_value = Some(v),
+            FirestoreValue::GeoPoint(v) => {
+                value.geo_point_value = Some(dto::LatLng {
+                    latitude: Some(v.latitude),
+                    longitude: Some(v.longitude),
+                })
+            }
+            FirestoreValue::Array(v) => {
+                value.array_value = Some(dto::ArrayValue {
+                    values: Some(v.into_iter().map(Into::into).collect()),
+                })
+            }
+            FirestoreValue::Map(v) => {
+                value.map_value = Some(dto::MapValue {
+                    fields: v.into_iter().map(|(k, v)| (k, v.into())).collect(),
+                })
+            }
+        }
+        value
+    }
+}
+
+/// A string that is never detected as timestamp, see [`PLAIN_STRING_TOKEN`]
+struct PlainString<'a>(&'a str);
+
+impl<'a> Serialize for PlainString<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(PLAIN_STRING_TOKEN, self.0)
+    }
+}
+
+impl Serialize for FirestoreValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            FirestoreValue::Null => serializer.serialize_unit(),
+            FirestoreValue::Bool(v) => serializer.serialize_bool(*v),
+            FirestoreValue::Integer(v) => serializer.serialize_i64(*v),
+            FirestoreValue::Double(v) => serializer.serialize_f64(*v),
+            FirestoreValue::Timestamp(v) => Timestamp(*v).serialize(serializer),
+            FirestoreValue::String(v) => PlainString(v).serialize(serializer),
+            FirestoreValue::Bytes(v) => serialize_marker(serializer, BYTES_KEY, &base64::encode(v)),
+            FirestoreValue::Reference(v) => serialize_marker(serializer, REFERENCE_KEY, v),
+            FirestoreValue::GeoPoint(v) => v.serialize(serializer),
+            FirestoreValue::Array(v) => v.serialize(serializer),
+            FirestoreValue::Map(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FirestoreValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(FIRESTORE_VALUE_TOKEN, FirestoreValueVisitor)
+    }
+}
+
+struct FirestoreValueVisitor;
+
+impl<'de> Visitor<'de> for FirestoreValueVisitor {
+    type Value = FirestoreValue;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a Firestore value")
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<FirestoreValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<FirestoreValue, D::Error> {
+        FirestoreValue::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<FirestoreValue, E> {
+        i64::try_from(v)
+            .map(FirestoreValue::Integer)
+            .map_err(|_| E::custom("The integer does not fit into 64 bit"))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<FirestoreValue, E> {
+        Ok(FirestoreValue::Bytes(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<FirestoreValue, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(FirestoreValue::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<FirestoreValue, A::Error> {
+        let mut fields = HashMap::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some((key, value)) = map.next_entry::<String, FirestoreValue>()? {
+            fields.insert(key, value);
+        }
+        marker_to_firestore_value(fields).map_err(de::Error::custom)
+    }
+}
+
+/// Converts a map with a single, reserved key into the corresponding value. All other maps are returned as they are.
+fn marker_to_firestore_value(fields: HashMap<String, FirestoreValue>) -> std::result::Result<FirestoreValue, String> {
+    if fields.len() != 1 {
+        return Ok(FirestoreValue::Map(fields));
+    }
+    let (key, value) = fields.iter().next().unwrap();
+    let invalid = || format!("Invalid {} value", key);
+    Ok(match (key.as_str(), value) {
+        (TIMESTAMP_KEY, FirestoreValue::String(v)) => FirestoreValue::Timestamp(
+            DateTime::parse_from_rfc3339(v)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+        ),
+        (BYTES_KEY, FirestoreValue::String(v)) => FirestoreValue::Bytes(base64::decode(v).map_err(|_| invalid())?),
+        (REFERENCE_KEY, FirestoreValue::String(v)) => FirestoreValue::Reference(v.clone()),
+        (GEO_POINT_KEY, FirestoreValue::Map(v)) => {
+            let coordinate = |name: &str| v.get(name).and_then(FirestoreValue::as_f64).ok_or_else(invalid);
+            FirestoreValue::GeoPoint(GeoPoint::new(coordinate("latitude")?, coordinate("longitude")?))
+        }
+        _ => FirestoreValue::Map(fields),
+    })
+}
+
+#[test]
+fn firestore_value_test() {
+    use crate::firebase_rest_to_rust::{document_to_pod, pod_to_document};
+
+    let mut doc = FirestoreValue::Map(HashMap::new());
+    doc.set("a_string", "2020-01-22T10:00:00Z").unwrap();
+    doc.set("a_map.`a.b`.an_int", 12).unwrap();
+    doc.set("a_timestamp", Timestamp("2020-01-22T10:00:00.5Z".parse().unwrap()))
+        .unwrap();
+    doc.set("bytes", vec![0u8, 255]).unwrap();
+    doc.set("reference", DocumentReference::new("p", "users/u1")).unwrap();
+    doc.set("location", GeoPoint::new(1.5, 0.0)).unwrap();
+    doc.set("list", vec![FirestoreValue::Null, 1.5.into()]).unwrap();
+    assert!(doc.set("a_string.x", 1).is_err());
+
+    assert_eq!(doc.get("a_map.`a.b`.an_int"), Some(&FirestoreValue::Integer(12)));
+    assert_eq!(doc.get("a_map.a.b"), None);
+
+    let document = pod_to_document(&doc).unwrap();
+    let fields = document.fields.as_ref().unwrap();
+    assert!(fields["a_string"].string_value.is_some());
+    assert!(fields["a_timestamp"].timestamp_value.is_some());
+    assert!(fields["bytes"].bytes_value.is_some());
+    assert!(fields["reference"].reference_value.is_some());
+    assert!(fields["location"].geo_point_value.is_some());
+
+    let read: FirestoreValue = document_to_pod(&document).unwrap();
+    assert_eq!(read, doc);
+
+    let value: dto::Value = doc.clone().into();
+    assert_eq!(FirestoreValue::try_from(&value).unwrap(), doc);
+
+    assert_eq!(doc.remove("a_map.`a.b`.an_int"), Some(FirestoreValue::Integer(12)));
+    assert_eq!(doc.get("a_map.`a.b`"), Some(&FirestoreValue::Map(HashMap::new())));
+}
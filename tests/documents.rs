@@ -114,7 +114,10 @@ fn service_account_session() -> errors::Result<()> {
         "tests",
         Some("service_test"),
         &obj,
-        documents::WriteOptions { merge: true },
+        documents::WriteOptions {
+            merge: true,
+            ..Default::default()
+        },
     )?;
 
     println!("Read and compare document");
@@ -125,6 +128,84 @@ fn service_account_session() -> errors::Result<()> {
     // Should still exist, because of the merge
     assert_eq!(read.a_string, Some("abcd".to_owned()));
 
+    println!("Merge a nested map key and delete a field");
+    let mut b_map = HashMap::<String, DemoMapDTO>::default();
+    b_map.insert(
+        "b.c".to_string(),
+        DemoMapDTO {
+            a_int: 13,
+            a_map: HashMap::default(),
+        },
+    );
+    let obj = DemoDTOPartial {
+        a_string: None,
+        an_int: 17,
+        a_map: b_map,
+    };
+    documents::write(
+        &mut session,
+        "tests",
+        Some("service_test"),
+        &obj,
+        documents::WriteOptions {
+            merge: true,
+            transforms: vec![("a_string".to_owned(), documents::FieldValue::Delete)],
+            ..Default::default()
+        },
+    )?;
+
+    let read: DemoDTOPartial = documents::read_with_options(
+        &mut session,
+        "tests",
+        "service_test",
+        documents::ReadOptions {
+            mask: Some(vec!["an_int".to_owned(), "a_map".to_owned(), "a_string".to_owned()]),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(read.an_int, 17);
+    assert_eq!(read.a_string, None);
+    // Both map keys exist, because of the deep merge
+    assert_eq!(read.a_map.get("a").unwrap().a_int, 12);
+    assert_eq!(read.a_map.get("b.c").unwrap().a_int, 13);
+
+    println!("Merge explicit field paths");
+    documents::write(
+        &mut session,
+        "tests",
+        Some("service_test"),
+        &obj,
+        documents::WriteOptions {
+            merge_fields: vec![documents::field_path(&["a_map", "b.c", "a_int"])],
+            ..Default::default()
+        },
+    )?;
+
+    println!("Read snapshots");
+    let snapshot: documents::DocumentSnapshot<DemoDTOPartial> =
+        documents::read_snapshot(&session, "tests", "service_test", documents::ReadOptions::default())?;
+    assert!(snapshot.exists());
+    assert_eq!(snapshot.id(), "service_test");
+    assert_eq!(snapshot.path(), "tests/service_test");
+    assert!(snapshot.update_time.is_some());
+
+    let snapshot: documents::DocumentSnapshot<DemoDTOPartial> =
+        documents::read_snapshot(&session, "tests", "non_existing", documents::ReadOptions::default())?;
+    assert!(!snapshot.exists());
+
+    println!("Optional reads");
+    let read: Option<DemoDTOPartial> = documents::read_opt(&session, "tests", "non_existing")?;
+    assert!(read.is_none());
+    let read: Option<DemoDTOPartial> = documents::read_opt(&session, "tests", "service_test")?;
+    assert_eq!(read.unwrap().an_int, 17);
+    assert!(documents::exists(&session, "tests/service_test")?);
+    assert!(!documents::exists(&session, "tests/non_existing")?);
+
+    let list_it: documents::List<DemoDTOPartial, _> = documents::list(&session, "tests");
+    for snapshot in list_it.snapshots() {
+        assert!(snapshot?.create_time.is_some());
+    }
+
     Ok(())
 }
 
@@ -220,6 +301,32 @@ fn user_account_session() -> errors::Result<()> {
     let doc: DemoDTO = documents::read_by_name(&user_session, &results.get(0).unwrap().name)?;
     assert_eq!(doc.a_string, "abc");
 
+    println!("user::Session documents::query_where with or");
+    let filter = documents::field("a_string")
+        .in_values(vec!["x".into(), "y".into()])?
+        .or(documents::field("an_int").eq(12)?);
+    let results: Vec<dto::Document> = documents::query_where(&user_session, "tests", Some(filter), None)?.collect();
+    assert_eq!(results.len(), 1);
+    let filter = documents::field("a_string")
+        .neq("abc")?
+        .or(documents::field("an_int").is_null());
+    let results: Vec<dto::Document> = documents::query_where(&user_session, "tests", Some(filter), None)?.collect();
+    assert_eq!(results.len(), 0);
+
+    println!("user::Session documents::list_with_options");
+    let list_it: documents::List<DemoDTOPartial, _> = documents::list_with_options(
+        &user_session,
+        "tests".to_owned(),
+        documents::ListOptions {
+            mask: Some(vec!["an_int".to_owned()]),
+            ..Default::default()
+        },
+    );
+    for doc in list_it {
+        let (doc, _) = doc?;
+        assert!(doc.a_string.is_none());
+    }
+
     println!("user::Session documents::list");
     let mut count = 0;
     let list_it: documents::List<DemoDTO, _> = documents::list(&user_session, "tests".to_owned());
@@ -236,6 +343,45 @@ fn user_account_session() -> errors::Result<()> {
 
     assert_eq!(results.len(), 1);
 
+    println!("user::Session documents::QueryBuilder");
+    let results: Vec<dto::Document> = documents::QueryBuilder::collection("tests")
+        .filter(documents::field("a_string").eq("abc")?)
+        .and(documents::field("an_int").gte(12)?)
+        .limit(10)
+        .run(&user_session)?
+        .collect();
+    assert_eq!(results.len(), 1);
+    let doc: DemoDTO = firebase_rest_to_rust::document_to_pod(&results[0])?;
+    assert_eq!(doc.an_int, 12);
+
+    println!("user::Session documents::QueryBuilder::run_typed");
+    let query = documents::QueryBuilder::collection("tests").filter(documents::field("a_string").eq("abc")?);
+    let results: Vec<documents::DocumentSnapshot<DemoDTO>> =
+        query.run_typed(&user_session)?.collect::<errors::Result<_>>()?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].data.as_ref().unwrap().an_int, 12);
+    assert_eq!(results[0].parent(), "tests");
+    assert!(results[0].create_time.is_some());
+
+    let results: Vec<documents::DocumentSnapshot<DemoDTOPartial>> = query
+        .clone()
+        .select(&["an_int"])
+        .run_typed(&user_session)?
+        .collect::<errors::Result<_>>()?;
+    let doc = results[0].data.as_ref().unwrap();
+    assert_eq!(doc.an_int, 12);
+    assert!(doc.a_string.is_none());
+
+    println!("user::Session documents::QueryBuilder::read_options");
+    let results: Vec<dto::Document> = query
+        .read_options(documents::ReadOptions {
+            read_time: Some(chrono::Utc::now()),
+            ..Default::default()
+        })
+        .run(&user_session)?
+        .collect();
+    assert_eq!(results.len(), 1);
+
     // test if the call fails for a non existing document
     println!("user::Session documents::delete");
     let r = documents::delete(&user_session, "tests/non_existing", true);
@@ -388,7 +534,10 @@ fn async_service_session() -> errors::Result<()> {
         "tests",
         Some("service_test"),
         &obj,
-        documents::WriteOptions { merge: true },
+        documents::WriteOptions {
+            merge: true,
+            ..Default::default()
+        },
     ))?;
 
     println!("Read and compare document");
@@ -419,5 +568,397 @@ fn async_service_session() -> errors::Result<()> {
         .collect();
     assert_eq!(results.len(), 1);
 
+    println!("List documents");
+    let results: Vec<(DemoDTOPartial, dto::Document)> = sys.block_on(async {
+        use futures::TryStreamExt;
+        documents::list_async(&session, "tests").try_collect().await
+    })?;
+    assert!(results.iter().any(|(_, meta)| meta.name.ends_with("/service_test_2")));
+
+    println!("List document snapshots");
+    let results: Vec<documents::DocumentSnapshot<DemoDTOPartial>> = sys.block_on(async {
+        use futures::TryStreamExt;
+        documents::list_snapshots_async(&session, "tests").try_collect().await
+    })?;
+    assert!(results.iter().any(|snapshot| snapshot.id() == "service_test_2"));
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CounterDTO {
+    value: i64,
+}
+
+#[test]
+fn transaction() -> errors::Result<()> {
+    let cred = credentials::Credentials::from_file("firebase-service-account.json").expect("Read credentials file");
+    let session = ServiceSession::new(cred).unwrap();
+
+    documents::write(
+        &session,
+        "tests",
+        Some("transaction_counter"),
+        &CounterDTO { value: 1 },
+        documents::WriteOptions::default(),
+    )?;
+
+    println!("Increment counter in a transaction");
+    let value = documents::run_transaction(&session, |tx| {
+        let counter: CounterDTO = tx.read("tests", "transaction_counter")?;
+        let counter = CounterDTO {
+            value: counter.value + 1,
+        };
+        tx.write(
+            "tests",
+            "transaction_counter",
+            &counter,
+            documents::WriteOptions::default(),
+        )?;
+        Ok(counter.value)
+    })?;
+    assert_eq!(value, 2);
+
+    let read: CounterDTO = documents::read(&session, "tests", "transaction_counter")?;
+    assert_eq!(read.value, 2);
+
+    println!("Failing transaction is rolled back");
+    let r: errors::Result<()> = documents::run_transaction(&session, |tx| {
+        tx.delete("tests/transaction_counter");
+        Err(FirebaseError::Generic("Abort transaction"))
+    });
+    assert!(r.is_err());
+
+    let read: CounterDTO = documents::read(&session, "tests", "transaction_counter")?;
+    assert_eq!(read.value, 2);
+
+    println!("Increment counter in an async transaction");
+    let mut sys = Runtime::new()?;
+    let value = sys.block_on(documents::run_transaction_async(&session, |tx| async move {
+        let counter: CounterDTO = tx.read_async("tests", "transaction_counter").await?;
+        let counter = CounterDTO {
+            value: counter.value + 1,
+        };
+        tx.write(
+            "tests",
+            "transaction_counter",
+            &counter,
+            documents::WriteOptions::default(),
+        )?;
+        Ok(counter.value)
+    }))?;
+    assert_eq!(value, 3);
+
+    documents::delete(&session, "tests/transaction_counter", true)?;
+
+    Ok(())
+}
+
+#[test]
+fn write_batch() -> errors::Result<()> {
+    let cred = credentials::Credentials::from_file("firebase-service-account.json").expect("Read credentials file");
+    let session = ServiceSession::new(cred).unwrap();
+
+    documents::write(
+        &session,
+        "tests",
+        Some("batch_b"),
+        &CounterDTO { value: 1 },
+        documents::WriteOptions::default(),
+    )?;
+
+    println!("Commit write batch");
+    let mut batch = documents::WriteBatch::new(&session);
+    batch
+        .set("tests", "batch_a", &CounterDTO { value: 10 })?
+        .update("tests", "batch_b", &CounterDTO { value: 20 }, &["value"])?
+        .create("tests", "batch_c", &CounterDTO { value: 30 })?;
+    assert_eq!(batch.len(), 3);
+    let results = batch.commit()?;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|r| r.update_time.is_some()));
+
+    let read: CounterDTO = documents::read(&session, "tests", "batch_b")?;
+    assert_eq!(read.value, 20);
+
+    println!("Batch read");
+    let result = documents::batch_read::<CounterDTO>(
+        &session,
+        &["tests/batch_a", "tests/batch_c", "tests/batch_non_existing"],
+        documents::ReadOptions::default(),
+    )?;
+    assert_eq!(result.found.len(), 2);
+    assert!(result.found.iter().all(|s| s.exists() && s.update_time.is_some()));
+    assert_eq!(result.missing.len(), 1);
+    assert!(result.missing[0].ends_with("tests/batch_non_existing"));
+
+    println!("A failing write aborts the entire batch");
+    let mut batch = documents::WriteBatch::new(&session);
+    batch
+        .delete("tests/batch_a")
+        .create("tests", "batch_c", &CounterDTO { value: 31 })?;
+    assert!(batch.commit().is_err());
+
+    let read: CounterDTO = documents::read(&session, "tests", "batch_a")?;
+    assert_eq!(read.value, 10);
+
+    let mut batch = documents::WriteBatch::new(&session);
+    batch
+        .delete("tests/batch_a")
+        .delete("tests/batch_b")
+        .delete("tests/batch_c");
+    batch.commit()?;
+
+    Ok(())
+}
+
+#[test]
+fn field_transforms() -> errors::Result<()> {
+    let cred = credentials::Credentials::from_file("firebase-service-account.json").expect("Read credentials file");
+    let session = ServiceSession::new(cred).unwrap();
+
+    println!("Write document with field transforms");
+    let result = documents::write(
+        &session,
+        "tests",
+        Some("transform_counter"),
+        &CounterDTO { value: 1 },
+        documents::WriteOptions {
+            transforms: vec![
+                ("value".to_owned(), documents::FieldValue::Increment(2.into())),
+                ("updated".to_owned(), documents::FieldValue::ServerTimestamp),
+            ],
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(result.document_id, "transform_counter");
+    assert_eq!(result.transform_results.len(), 2);
+    assert_eq!(result.transform_results[0].integer_value.as_ref().unwrap(), "3");
+    assert!(result.transform_results[1].timestamp_value.is_some());
+
+    println!("Apply field transforms only");
+    let result = documents::transform(
+        &session,
+        "tests",
+        "transform_counter",
+        &[
+            ("value".to_owned(), documents::FieldValue::Increment(1.into())),
+            (
+                "tags".to_owned(),
+                documents::FieldValue::ArrayUnion(vec!["a".into(), "b".into()]),
+            ),
+        ],
+    )?;
+    assert_eq!(result.transform_results[0].integer_value.as_ref().unwrap(), "4");
+
+    let read: CounterDTO = documents::read(&session, "tests", "transform_counter")?;
+    assert_eq!(read.value, 4);
+
+    documents::delete(&session, "tests/transform_counter", true)?;
+
+    Ok(())
+}
+
+#[test]
+fn collection_group_query() -> errors::Result<()> {
+    let cred = credentials::Credentials::from_file("firebase-service-account.json").expect("Read credentials file");
+    let session = ServiceSession::new(cred).unwrap();
+
+    for parent in &["tests/group/shops/a/reviews", "tests/group/shops/b/reviews"] {
+        documents::write(
+            &session,
+            parent,
+            Some("review"),
+            &CounterDTO { value: 5 },
+            documents::WriteOptions::default(),
+        )?;
+    }
+
+    println!("Collection group query");
+    let count = documents::QueryBuilder::collection_group("reviews")
+        .parent("tests/group")
+        .filter(documents::field("value").eq(5)?)
+        .run(&session)?
+        .count();
+    assert_eq!(count, 2);
+
+    println!("Paginated collection group query");
+    let query = documents::QueryBuilder::collection_group("reviews")
+        .parent("tests/group")
+        .filter(documents::field("value").gte(5)?);
+    let names: Vec<String> = query
+        .paginate(&session, 1)
+        .map(|document| document.map(|d| d.name))
+        .collect::<errors::Result<_>>()?;
+    assert_eq!(names.len(), 2);
+    assert_ne!(names[0], names[1]);
+    let snapshots: Vec<documents::DocumentSnapshot<CounterDTO>> =
+        query.paginate_typed(&session, 1).collect::<errors::Result<_>>()?;
+    assert!(snapshots.iter().all(|s| s.data.as_ref().map(|d| d.value) == Some(5)));
+    assert_eq!(query.clone().limit(1).paginate(&session, 5).count(), 1);
+
+    println!("Partitioned query");
+    let group = documents::QueryBuilder::collection_group("reviews").parent("tests/group");
+    let mut count = 0;
+    for partition in documents::partition_query(&session, &group, 2)? {
+        count += partition.apply(group.clone()).run(&session)?.count();
+    }
+    assert_eq!(count, 2);
+
+    println!("List missing documents");
+    let list: documents::List<HashMap<String, i64>, _> = documents::list_with_options(
+        &session,
+        "tests/group/shops",
+        documents::ListOptions {
+            page_size: Some(1),
+            order_by: Some(vec![("__name__".to_owned(), false)]),
+            show_missing: true,
+            ..Default::default()
+        },
+    );
+    let names: Vec<String> = list
+        .map(|result| result.map(|(_, meta)| meta.name))
+        .collect::<errors::Result<_>>()?;
+    assert_eq!(names.len(), 2);
+    assert!(names[0].ends_with("/shops/b"));
+
+    println!("Aggregation query");
+    assert_eq!(documents::count(&session, &query)?, 2);
+    assert_eq!(
+        documents::sum(&session, &query, "value")?,
+        values::FirestoreValue::Integer(10)
+    );
+    let result = query
+        .aggregate()
+        .count("count")
+        .sum("total", "value")
+        .avg("average", "value")
+        .run(&session)?;
+    assert_eq!(result.count("count"), Some(2));
+    assert_eq!(result.get_f64("total"), Some(10.0));
+    assert_eq!(result.get_f64("average"), Some(5.0));
+
+    println!("Query scoped to a parent document");
+    let count = documents::QueryBuilder::collection("reviews")
+        .parent("tests/group/shops/a")
+        .run(&session)?
+        .count();
+    assert_eq!(count, 1);
+
+    println!("List collection ids");
+    let collection_ids: Vec<String> =
+        documents::list_collection_ids(&session, "tests/group").collect::<errors::Result<_>>()?;
+    assert_eq!(collection_ids, vec!["shops".to_owned()]);
+    let collection_ids: Vec<String> = Runtime::new()?.block_on(async {
+        use futures::TryStreamExt;
+        documents::list_collection_ids_async(&session, "tests/group")
+            .try_collect()
+            .await
+    })?;
+    assert_eq!(collection_ids, vec!["shops".to_owned()]);
+
+    println!("Walk document tree");
+    let entries: Vec<documents::WalkEntry> = documents::walk(&session, "tests/group").collect::<errors::Result<_>>()?;
+    let collections = entries
+        .iter()
+        .filter(|e| match e {
+            documents::WalkEntry::Collection(_) => true,
+            _ => false,
+        })
+        .count();
+    assert_eq!(collections, 3);
+    assert_eq!(entries.len(), 5);
+
+    println!("Delete collection with a filter");
+    let progress = documents::delete_collection(
+        &session,
+        "tests/group/shops/a/reviews",
+        Some(documents::field("value").eq(5)?),
+        |_| {},
+    )?;
+    assert_eq!(progress.deleted_documents, 1);
+
+    println!("Delete collection with a filter (async)");
+    let mut rt = Runtime::new()?;
+    let progress = rt.block_on(documents::delete_collection_async(
+        &session,
+        "tests/group/shops/b/reviews",
+        Some(documents::field("value").eq(5)?),
+        |_| {},
+    ))?;
+    assert_eq!(progress.deleted_documents, 1);
+
+    println!("Delete recursive");
+    let mut reports = 0;
+    documents::delete_recursive(&session, "tests/group", |_| reports += 1)?;
+    assert_eq!(reports, 1);
+    assert_eq!(documents::walk(&session, "tests/group").count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn preconditions() -> errors::Result<()> {
+    let cred = credentials::Credentials::from_file("firebase-service-account.json").expect("Read credentials file");
+    let session = ServiceSession::new(cred).unwrap();
+
+    documents::delete(&session, "tests/precondition", false)?;
+
+    println!("Write with must_not_exist");
+    let first = documents::write(
+        &session,
+        "tests",
+        Some("precondition"),
+        &CounterDTO { value: 1 },
+        documents::WriteOptions::default().must_not_exist(),
+    )?;
+    let r = documents::write(
+        &session,
+        "tests",
+        Some("precondition"),
+        &CounterDTO { value: 1 },
+        documents::WriteOptions::default().must_not_exist(),
+    );
+    match r {
+        Err(FirebaseError::FailedPrecondition(_, context)) => assert_eq!(context, "precondition"),
+        _ => panic!("Expected a FailedPrecondition error"),
+    };
+
+    println!("Compare and swap with if_update_time");
+    let second = documents::write(
+        &session,
+        "tests",
+        Some("precondition"),
+        &CounterDTO { value: 2 },
+        documents::WriteOptions::default().if_update_time(first.update_time.unwrap()),
+    )?;
+    let r = documents::write(
+        &session,
+        "tests",
+        Some("precondition"),
+        &CounterDTO { value: 3 },
+        documents::WriteOptions::default().if_update_time(first.update_time.unwrap()),
+    );
+    assert!(match r {
+        Err(FirebaseError::FailedPrecondition(_, _)) => true,
+        _ => false,
+    });
+
+    let read: CounterDTO = documents::read(&session, "tests", "precondition")?;
+    assert_eq!(read.value, 2);
+
+    println!("Delete with precondition");
+    let r = documents::delete_with_precondition(
+        &session,
+        "tests/precondition",
+        documents::Precondition::UpdateTime(first.update_time.unwrap()),
+    );
+    assert!(r.is_err());
+    documents::delete_with_precondition(
+        &session,
+        "tests/precondition",
+        documents::Precondition::UpdateTime(second.update_time.unwrap()),
+    )?;
+
     Ok(())
 }